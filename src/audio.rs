@@ -0,0 +1,168 @@
+use std::f64::consts::PI;
+use sdl2::mixer::{self, Channel, Chunk};
+use sdl2::AudioSubsystem;
+use crate::shapes::Point;
+use crate::camera::Camera;
+use crate::music::{self, Music};
+use crate::world::GameEvent;
+use crate::FIELD_WIDTH;
+
+const FREQUENCY: i32 = 44100;
+// Channels for sound effects, the music gets its own on top of these
+const CHANNELS: i32 = 16;
+// Impulse from solve_polygon_collision that plays an impact at full volume
+const IMPACT_FULL_VOLUME: f64 = 2.0e6;
+// Quieter impacts than this are resting contact and stay silent
+const IMPACT_MIN_VOLUME: f64 = 0.05;
+
+struct Sounds {
+    laser: Chunk,
+    split: Chunk,
+    destroy: Chunk,
+    impact: Chunk,
+    death: Chunk,
+}
+
+// Small deterministic noise source so the synthesized sounds are the same every run. Never runs out.
+pub struct Noise(pub u32);
+
+impl Iterator for Noise {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        return Some(self.0 as f64 / u32::MAX as f64 * 2.0 - 1.0);
+    }
+}
+
+// Renders a mono waveform of the given length into a chunk of signed 16 bit samples, the format the mixer is opened with
+pub fn synth(rate: i32, channels: i32, length: f64, mut f: impl FnMut(f64) -> f64) -> Result<Chunk, String> {
+    let samples = (rate as f64 * length) as usize;
+    let mut buffer = Vec::with_capacity(samples * channels as usize);
+    for i in 0..samples {
+        let t = i as f64 / rate as f64;
+        let v = (f(t).clamp(-1.0, 1.0) * i16::MAX as f64) as i16;
+        for _ in 0..channels {
+            buffer.push(v);
+        }
+    }
+    return Chunk::from_raw_buffer(buffer.into_boxed_slice());
+}
+
+impl Sounds {
+    fn synthesize(rate: i32, channels: i32) -> Result<Sounds, String> {
+        let mut noise = Noise(0x2545f491);
+        let laser = synth(rate, channels, 0.2, |t| {
+            let freq = 1800.0 * (1.0 - t / 0.2) + 300.0;
+            let phase = (2.0 * PI * freq * t).sin();
+            0.4 * phase.signum() * (-t * 15.0).exp()
+        })?;
+        let split = synth(rate, channels, 0.25, |t| {
+            (0.5 * noise.next().unwrap() + 0.5 * (2.0 * PI * 140.0 * t).sin()) * (-t * 18.0).exp()
+        })?;
+        let destroy = synth(rate, channels, 0.15, |t| {
+            0.6 * noise.next().unwrap() * (-t * 30.0).exp()
+        })?;
+        let impact = synth(rate, channels, 0.35, |t| {
+            (0.8 * (2.0 * PI * (90.0 - 100.0 * t) * t).sin() + 0.2 * noise.next().unwrap()) * (-t * 12.0).exp()
+        })?;
+        let death = synth(rate, channels, 1.0, |t| {
+            (0.6 * noise.next().unwrap() + 0.4 * (2.0 * PI * 220.0 * (1.0 - t * 0.7) * t).sin()) * (-t * 3.5).exp()
+        })?;
+        return Ok(Sounds { laser, split, destroy, impact, death });
+    }
+}
+
+// Sound effects for world events and the music. Everything is synthesized at start up so no asset files are needed.
+// Without a usable audio device, including SDL's dummy driver, the game just stays silent.
+pub struct Audio {
+    sounds: Option<Sounds>,
+    music: Option<Music>,
+    pub music_volume: f64,
+    pub effects_volume: f64,
+}
+
+impl Audio {
+    pub fn new(subsystem: Option<&AudioSubsystem>, music_volume: f64, effects_volume: f64) -> Audio {
+        let silent = Audio { sounds: None, music: None, music_volume, effects_volume };
+        match subsystem {
+            Some(s) if s.current_audio_driver() != "dummy" => {},
+            _ => return silent
+        }
+        if let Err(e) = mixer::open_audio(FREQUENCY, mixer::AUDIO_S16SYS, 2, 1024) {
+            eprintln!("Could not open audio, sound is disabled: {}", e);
+            return silent;
+        }
+        mixer::allocate_channels(CHANNELS + music::LAYERS as i32);
+        // The music plays on the first channels, effects only get the ones after them
+        mixer::reserve_channels(music::LAYERS as i32);
+        // The mixer may pick a different rate or channel count than asked for, synth only writes one sample format
+        let sounds = mixer::query_spec().and_then(|(rate, format, channels)| {
+            if format != mixer::AUDIO_S16SYS {
+                return Err(format!("unsupported sample format {:#x}", format));
+            }
+            Ok((Sounds::synthesize(rate, channels)?, Music::synthesize(rate, channels)?))
+        });
+        match sounds {
+            Ok((sounds, music)) => Audio { sounds: Some(sounds), music: Some(music), music_volume, effects_volume },
+            Err(e) => {
+                eprintln!("Could not create sounds, sound is disabled: {}", e);
+                mixer::close_audio();
+                silent
+            }
+        }
+    }
+
+    pub fn change_music_volume(&mut self, change: f64) {
+        self.music_volume = (self.music_volume + change).clamp(0.0, 1.0);
+    }
+
+    pub fn change_effects_volume(&mut self, change: f64) {
+        self.effects_volume = (self.effects_volume + change).clamp(0.0, 1.0);
+    }
+
+    // Fades the music layers towards the given intensity, see music::intensity
+    pub fn update_music(&mut self, intensity: f64, delta: f64) {
+        if let Some(music) = &mut self.music {
+            music.update(intensity, delta, self.music_volume);
+        }
+    }
+
+    // Sounds are panned by where on the screen they happen
+    fn play(&self, chunk: &Chunk, volume: f64, pos: Point, camera: &Camera) {
+        // Running out of free channels just drops the sound
+        if let Ok(channel) = Channel::all().play(chunk, 0) {
+            channel.set_volume((volume * self.effects_volume * mixer::MAX_VOLUME as f64) as i32);
+            let right = (camera.to_screen(pos).x / FIELD_WIDTH).clamp(0.0, 1.0);
+            let _ = channel.set_panning((255.0 * (1.0 - right * 0.6)) as u8, (255.0 * (0.4 + right * 0.6)) as u8);
+        }
+    }
+
+    pub fn play_events(&self, events: &[GameEvent], camera: &Camera) {
+        let sounds = match &self.sounds {
+            Some(sounds) => sounds,
+            None => return
+        };
+        // Many contacts can happen in one frame, only the hardest one is heard
+        let mut impact: Option<(f64, Point)> = None;
+        for event in events {
+            match *event {
+                GameEvent::LaserFired { pos } => self.play(&sounds.laser, 0.6, pos, camera),
+                GameEvent::AsteroidSplit { start, end } => self.play(&sounds.split, 0.8, (start + end) / 2.0, camera),
+                GameEvent::FragmentDestroyed { pos, area } => self.play(&sounds.destroy, (0.4 + area / 1000.0).min(0.8), pos, camera),
+                GameEvent::Impact { pos, impulse, .. } => {
+                    let volume = impulse / IMPACT_FULL_VOLUME;
+                    if impact.is_none_or(|(v, _)| volume > v) {
+                        impact = Some((volume, pos));
+                    }
+                },
+                GameEvent::PlayerDied { pos } => self.play(&sounds.death, 1.0, pos, camera),
+            }
+        }
+        if let Some((volume, pos)) = impact.filter(|(v, _)| *v >= IMPACT_MIN_VOLUME) {
+            self.play(&sounds.impact, volume.min(1.0), pos, camera);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sdl2::image::LoadTexture;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
+use crate::camera::Camera;
+use crate::shapes::Point;
+use crate::world::World;
+use crate::{FIELD_WIDTH, FIELD_HEIGHT};
+
+// What is drawn behind a stage of a run, stages past the end of the list start over from the first
+struct Backdrop {
+    image: Option<&'static str>,
+    image_tint: Color,
+    star_color: Color,
+}
+
+const BACKDROPS: [Backdrop; 3] = [
+    Backdrop { image: Some("cove_image.png"), image_tint: Color::RGB(0x80, 0x80, 0x80), star_color: Color::RGB(0xff, 0xff, 0xff) },
+    Backdrop { image: None, image_tint: Color::RGB(0xff, 0xff, 0xff), star_color: Color::RGB(0xa0, 0xc0, 0xff) },
+    Backdrop { image: Some("cove_image.png"), image_tint: Color::RGB(0x70, 0x40, 0x40), star_color: Color::RGB(0xff, 0xc0, 0xa0) },
+];
+
+// (stars, depth, size) for each layer from the back. Depth is how much of the camera's movement the layer follows.
+const LAYERS: [(usize, f64, u32); 3] = [(160, 0.1, 1), (80, 0.25, 2), (30, 0.5, 3)];
+
+// The stars only decorate, so they have their own fixed seed and never touch the world's RNG
+const STAR_SEED: u64 = 0x5eed_57a2;
+
+// Images are looked for next to the executable first, then in the working directory for `cargo run`
+fn asset_path(name: &str) -> PathBuf {
+    let installed = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.join(name)));
+    return installed.filter(|p| p.exists()).unwrap_or_else(|| PathBuf::from(name));
+}
+
+struct StarLayer {
+    stars: Vec<(Point, f64)>,
+    depth: f64,
+    size: u32,
+}
+
+// An image backdrop with parallax star layers on top. Stars scroll against the camera as it follows the player.
+// A missing or broken image leaves just the stars on black.
+pub struct Background<'a> {
+    images: Vec<(&'static str, Option<Texture<'a>>)>,
+    layers: Vec<StarLayer>,
+    backdrop: usize,
+}
+
+impl<'a> Background<'a> {
+    pub fn new<T>(creator: &'a TextureCreator<T>) -> Background<'a> {
+        let mut images: Vec<(&'static str, Option<Texture<'a>>)> = Vec::new();
+        for path in BACKDROPS.iter().filter_map(|b| b.image) {
+            if images.iter().any(|(p, _)| *p == path) {
+                continue;
+            }
+            let file = asset_path(path);
+            let texture = creator.load_texture(&file)
+                .map_err(|e| eprintln!("Could not load background '{}': {}", file.display(), e))
+                .ok();
+            images.push((path, texture));
+        }
+
+        let mut rng = StdRng::seed_from_u64(STAR_SEED);
+        let layers = LAYERS.iter().map(|&(count, depth, size)| StarLayer {
+            stars: (0..count).map(|_| {
+                let pos = Point::new(rng.gen::<f64>() * FIELD_WIDTH, rng.gen::<f64>() * FIELD_HEIGHT);
+                (pos, 0.3 + 0.7 * rng.gen::<f64>())
+            }).collect(),
+            depth,
+            size,
+        }).collect();
+        return Background { images, layers, backdrop: 0 };
+    }
+
+    // A new stage starts every time a timed meteoroid is due, so the backdrop changes along with the pace of the run
+    pub fn select(&mut self, world: &World) {
+        let stage = (world.time_alive / world.tuning.time_between_meteoroids) as usize;
+        self.backdrop = stage % BACKDROPS.len();
+    }
+
+    pub fn render<T: RenderTarget>(&mut self, canvas: &mut Canvas<T>, camera: &Camera) -> Result<(), String> {
+        let backdrop = &BACKDROPS[self.backdrop];
+        let image = backdrop.image.and_then(|path| self.images.iter_mut().find(|(p, _)| *p == path));
+        if let Some((_, Some(texture))) = image {
+            let tint = backdrop.image_tint;
+            texture.set_color_mod(tint.r, tint.g, tint.b);
+            canvas.copy(texture, None, Rect::new(0, 0, FIELD_WIDTH as u32, FIELD_HEIGHT as u32))?;
+        }
+
+        for layer in &self.layers {
+            let offset = camera.centre * -layer.depth;
+            for (pos, brightness) in &layer.stars {
+                let x = (pos.x + offset.x).rem_euclid(FIELD_WIDTH);
+                let y = (pos.y + offset.y).rem_euclid(FIELD_HEIGHT);
+                let c = backdrop.star_color;
+                canvas.set_draw_color(Color::RGB(
+                    (c.r as f64 * brightness) as u8, (c.g as f64 * brightness) as u8, (c.b as f64 * brightness) as u8
+                ));
+                canvas.fill_rect(Rect::new(x as i32, y as i32, layer.size, layer.size))?;
+            }
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,223 @@
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::mouse::MouseButton;
+use crate::config::{parse_entries, read_config};
+use crate::shapes::Point;
+use crate::world::InputEvent;
+
+pub const DEFAULT_PATH: &str = "bindings.cfg";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Reset,
+    Quit,
+    Pause,
+    MusicDown,
+    MusicUp,
+    EffectsDown,
+    EffectsUp,
+    ZoomIn,
+    ZoomOut,
+    Debug,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight,
+        Action::Fire, Action::Reset, Action::Quit, Action::Pause,
+        Action::MusicDown, Action::MusicUp, Action::EffectsDown, Action::EffectsUp,
+        Action::ZoomIn, Action::ZoomOut, Action::Debug
+    ];
+
+    pub fn is_movement(&self) -> bool {
+        return matches!(self, Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight);
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "MoveUp",
+            Action::MoveDown => "MoveDown",
+            Action::MoveLeft => "MoveLeft",
+            Action::MoveRight => "MoveRight",
+            Action::Fire => "Fire",
+            Action::Reset => "Reset",
+            Action::Quit => "Quit",
+            Action::Pause => "Pause",
+            Action::MusicDown => "MusicDown",
+            Action::MusicUp => "MusicUp",
+            Action::EffectsDown => "EffectsDown",
+            Action::EffectsUp => "EffectsUp",
+            Action::ZoomIn => "ZoomIn",
+            Action::ZoomOut => "ZoomOut",
+            Action::Debug => "Debug",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        return Action::ALL.iter().find(|a| a.name().eq_ignore_ascii_case(name)).copied();
+    }
+
+    // The world input an action turns into. target is where a shot goes,
+    // None fires along the controller aim instead.
+    pub fn input_event(&self, pressed: bool, target: Option<Point>) -> Option<InputEvent> {
+        let amount = if pressed { 1.0 } else { 0.0 };
+        match self {
+            Action::MoveUp => Some(InputEvent::Move(0, amount)),
+            Action::MoveDown => Some(InputEvent::Move(1, amount)),
+            Action::MoveLeft => Some(InputEvent::Move(2, amount)),
+            Action::MoveRight => Some(InputEvent::Move(3, amount)),
+            Action::Fire if pressed => match target {
+                Some(target) => Some(InputEvent::Fire(target)),
+                None => Some(InputEvent::FireAim)
+            },
+            Action::Reset if pressed => Some(InputEvent::Reset),
+            _ => None
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Binding {
+    Key(Keycode),
+    Scancode(Scancode),
+    Mouse(MouseButton),
+    Pad(Button),
+    Trigger(Axis),
+}
+
+impl Binding {
+    // Short name for on-screen hints
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(k) => k.name(),
+            Binding::Scancode(s) => s.name().to_string(),
+            Binding::Mouse(b) => format!("{:?} mouse", b).to_lowercase(),
+            Binding::Pad(b) => b.string(),
+            Binding::Trigger(a) => a.string(),
+        }
+    }
+
+    // "W" is a key by layout, "Scancode:W" the physical key in the W position,
+    // "Mouse:Left" a mouse button and "Pad:start" or "Pad:righttrigger" a controller input
+    pub fn parse(text: &str) -> Option<Binding> {
+        if let Some((kind, name)) = text.split_once(':') {
+            return match kind.trim().to_ascii_lowercase().as_str() {
+                "scancode" => Scancode::from_name(name.trim()).map(Binding::Scancode),
+                "mouse" => match name.trim().to_ascii_lowercase().as_str() {
+                    "left" => Some(Binding::Mouse(MouseButton::Left)),
+                    "middle" => Some(Binding::Mouse(MouseButton::Middle)),
+                    "right" => Some(Binding::Mouse(MouseButton::Right)),
+                    "x1" => Some(Binding::Mouse(MouseButton::X1)),
+                    "x2" => Some(Binding::Mouse(MouseButton::X2)),
+                    _ => None
+                },
+                "key" => Keycode::from_name(name.trim()).map(Binding::Key),
+                "pad" => match Axis::from_string(name.trim()) {
+                    Some(axis @ (Axis::TriggerLeft | Axis::TriggerRight)) => Some(Binding::Trigger(axis)),
+                    Some(_) => None,
+                    None => Button::from_string(name.trim()).map(Binding::Pad)
+                },
+                _ => None
+            };
+        }
+        return Keycode::from_name(text.trim()).map(Binding::Key);
+    }
+}
+
+pub struct Bindings {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings { bindings: vec![
+            (Binding::Key(Keycode::W), Action::MoveUp),
+            (Binding::Key(Keycode::S), Action::MoveDown),
+            (Binding::Key(Keycode::A), Action::MoveLeft),
+            (Binding::Key(Keycode::D), Action::MoveRight),
+            (Binding::Mouse(MouseButton::Left), Action::Fire),
+            (Binding::Mouse(MouseButton::Middle), Action::Fire),
+            (Binding::Mouse(MouseButton::Right), Action::Fire),
+            (Binding::Key(Keycode::R), Action::Reset),
+            (Binding::Key(Keycode::Escape), Action::Quit),
+            (Binding::Key(Keycode::P), Action::Pause),
+            (Binding::Key(Keycode::F5), Action::MusicDown),
+            (Binding::Key(Keycode::F6), Action::MusicUp),
+            (Binding::Key(Keycode::F7), Action::EffectsDown),
+            (Binding::Key(Keycode::F8), Action::EffectsUp),
+            (Binding::Key(Keycode::Equals), Action::ZoomIn),
+            (Binding::Key(Keycode::KpPlus), Action::ZoomIn),
+            (Binding::Key(Keycode::Minus), Action::ZoomOut),
+            (Binding::Key(Keycode::KpMinus), Action::ZoomOut),
+            (Binding::Key(Keycode::F3), Action::Debug),
+            (Binding::Trigger(Axis::TriggerRight), Action::Fire),
+            (Binding::Pad(Button::RightShoulder), Action::Fire),
+            (Binding::Pad(Button::Back), Action::Reset),
+            (Binding::Pad(Button::Start), Action::Pause),
+        ]}
+    }
+}
+
+impl Bindings {
+    pub fn load(path: &str, required: bool) -> Result<Bindings, String> {
+        match read_config(path, required)? {
+            Some(text) => Bindings::parse(&text, path),
+            None => Ok(Bindings::default())
+        }
+    }
+
+    // Each line is `Action = binding, binding, ...` and replaces the defaults for that action.
+    // An empty list leaves the action unbound.
+    pub fn parse(text: &str, source: &str) -> Result<Bindings, String> {
+        let mut bindings = Bindings::default();
+        for entry in parse_entries(text, source, 1)? {
+            let action = Action::from_name(&entry.key)
+                .ok_or(format!("{}:{}: unknown action '{}'", source, entry.line, entry.key))?;
+            bindings.bindings.retain(|(_, a)| *a != action);
+            for name in entry.value.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+                let binding = Binding::parse(name)
+                    .ok_or(format!("{}:{}: unknown key or button '{}' for {}", source, entry.line, name, action.name()))?;
+                bindings.bindings.push((binding, action));
+            }
+        }
+        return Ok(bindings);
+    }
+
+    pub fn key_action(&self, keycode: Option<Keycode>, scancode: Option<Scancode>) -> Option<Action> {
+        return self.bindings.iter().find(|(b, _)| match b {
+            Binding::Key(k) => Some(*k) == keycode,
+            Binding::Scancode(s) => Some(*s) == scancode,
+            _ => false
+        }).map(|(_, a)| *a);
+    }
+
+    // What is bound to an action, controller inputs are only named when there is nothing else
+    pub fn describe(&self, action: Action) -> String {
+        let bound = self.bindings.iter().filter(|(_, a)| *a == action).map(|(b, _)| b).collect::<Vec<_>>();
+        let desk = bound.iter().copied().filter(|b| !matches!(b, Binding::Pad(_) | Binding::Trigger(_))).collect::<Vec<_>>();
+        let shown = if desk.is_empty() { bound } else { desk };
+        if shown.is_empty() {
+            return "(unbound)".to_string();
+        }
+        return shown.iter().map(|b| b.label()).collect::<Vec<_>>().join(" or ");
+    }
+
+    // How to move and fire with the current bindings
+    pub fn controls_hint(&self) -> String {
+        let movement = [Action::MoveUp, Action::MoveLeft, Action::MoveDown, Action::MoveRight].map(|a| self.describe(a));
+        return format!("Move with {}, fire with {}", movement.join("/"), self.describe(Action::Fire));
+    }
+
+    pub fn mouse_action(&self, button: MouseButton) -> Option<Action> {
+        return self.action(Binding::Mouse(button));
+    }
+
+    pub fn action(&self, binding: Binding) -> Option<Action> {
+        return self.bindings.iter().find(|(b, _)| *b == binding).map(|(_, a)| *a);
+    }
+}
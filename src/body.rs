@@ -0,0 +1,344 @@
+use std::f64::consts::PI;
+use crate::shapes::{Point, Polygon, Rectangle};
+
+// Below this sliding speed a contact gets no friction, there is no direction to apply it in
+const MIN_SLIDE_SPEED: f64 = 1e-9;
+
+// The physical state of anything that takes part in collisions. The position is the centre of mass.
+// An infinite mass or inertia makes the body immovable or unable to turn, like the arena walls or the ship whose heading follows its velocity.
+#[derive(Copy, Clone, Debug)]
+pub struct RigidBody {
+    pub pos: Point,
+    pub angle: f64,
+    pub vel: Point,
+    pub ang_vel: f64,
+    pub mass: f64,
+    pub inertia: f64,
+    pub restitution: f64,
+    pub friction: f64,
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    return a.x * b.y - a.y * b.x;
+}
+
+impl RigidBody {
+    pub fn new(pos: Point, vel: Point, mass: f64, inertia: f64, restitution: f64, friction: f64) -> RigidBody {
+        RigidBody { pos, angle: 0.0, vel, ang_vel: 0.0, mass, inertia, restitution, friction }
+    }
+
+    // A body that nothing can move, for static geometry
+    pub fn fixed(pos: Point, restitution: f64, friction: f64) -> RigidBody {
+        return RigidBody::new(pos, Point::new(0.0, 0.0), f64::INFINITY, f64::INFINITY, restitution, friction);
+    }
+
+    fn inv_mass(&self) -> f64 {
+        return 1.0 / self.mass;
+    }
+
+    fn inv_inertia(&self) -> f64 {
+        return 1.0 / self.inertia;
+    }
+
+    // Velocity of the point p, which moves with the body
+    pub fn velocity_at(&self, p: Point) -> Point {
+        let r = p - self.pos;
+        return self.vel + Point::new(-self.ang_vel * r.y, self.ang_vel * r.x);
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Point, at: Point) {
+        self.vel = self.vel + impulse * self.inv_mass();
+        self.ang_vel += cross(at - self.pos, impulse) * self.inv_inertia();
+    }
+
+    // Moves the body along with its angle over delta seconds
+    pub fn integrate(&mut self, delta: f64) {
+        self.pos = self.pos + self.vel * delta;
+        self.angle += self.ang_vel * delta;
+    }
+
+    // Resistance of the body to an impulse at p along dir
+    fn effective_inv_mass(&self, p: Point, dir: Point) -> f64 {
+        let rn = cross(p - self.pos, dir);
+        return self.inv_mass() + rn * rn * self.inv_inertia();
+    }
+}
+
+// Applies equal and opposite impulses at the contact point p, where normal points from a towards b.
+// Restitution is that of an immovable body if there is one, so static geometry decides how hard things bounce off it,
+// and the higher of the two otherwise. Friction is the geometric mean and is limited by the normal impulse.
+// Returns the impulse along the normal, 0 if the bodies are already moving apart.
+pub fn solve_contact(a: &mut RigidBody, b: &mut RigidBody, p: Point, normal: Point) -> f64 {
+    let vrel = a.velocity_at(p) - b.velocity_at(p);
+    let vn = vrel.dot(normal);
+    if vn < 0.0 {
+        return 0.0;
+    }
+    let k = a.effective_inv_mass(p, normal) + b.effective_inv_mass(p, normal);
+    if k == 0.0 {
+        return 0.0;
+    }
+    let restitution = if a.mass.is_infinite() {
+        a.restitution
+    } else if b.mass.is_infinite() {
+        b.restitution
+    } else {
+        a.restitution.max(b.restitution)
+    };
+    let j = -(restitution + 1.0) * vn / k;
+    a.apply_impulse(normal * j, p);
+    b.apply_impulse(normal * -j, p);
+
+    let friction = (a.friction * b.friction).sqrt();
+    let slide = a.velocity_at(p) - b.velocity_at(p);
+    let tangent = slide - normal * slide.dot(normal);
+    let speed = tangent.len();
+    if friction > 0.0 && speed > MIN_SLIDE_SPEED {
+        let tangent = tangent / speed;
+        let kt = a.effective_inv_mass(p, tangent) + b.effective_inv_mass(p, tangent);
+        let jt = (-speed / kt).max(-friction * j.abs());
+        a.apply_impulse(tangent * jt, p);
+        b.apply_impulse(tangent * -jt, p);
+    }
+    return j;
+}
+
+// The outline moved to have its centre at the origin and turned back by angle
+fn body_space(outline: &Polygon, angle: f64) -> Polygon {
+    let origin = Point::new(0.0, 0.0);
+    return Polygon::new(outline.points.iter().map(|p| (*p - outline.centre).rotated(-angle, origin)).collect());
+}
+
+// A body with an outline. The outline is kept around the centre of mass and placed in the world from the body
+// whenever it moves, so the body is the only record of where it is. The transform at the last step is kept for drawing.
+pub struct Solid {
+    body: RigidBody,
+    // The outline at angle 0
+    local: Polygon,
+    shape: Polygon,
+    // Does not change as it turns, so it is only worked out when the outline changes
+    width: f64,
+    prev_pos: Point,
+    prev_angle: f64,
+}
+
+impl Solid {
+    // Takes an outline given in the world, the body moves to its centre
+    pub fn new(outline: Polygon, mut body: RigidBody) -> Solid {
+        body.pos = outline.centre;
+        return Solid {
+            local: body_space(&outline, body.angle),
+            width: outline.width(),
+            shape: outline,
+            body,
+            prev_pos: body.pos,
+            prev_angle: body.angle,
+        };
+    }
+
+    // Replaces the outline with one given in the world. The body moves to its centre and keeps its angle and velocity.
+    pub fn set_outline(&mut self, outline: Polygon) {
+        self.body.pos = outline.centre;
+        self.local = body_space(&outline, self.body.angle);
+        self.width = outline.width();
+        self.shape = outline;
+    }
+
+    pub fn set_mass(&mut self, mass: f64, inertia: f64) {
+        self.body.mass = mass;
+        self.body.inertia = inertia;
+    }
+
+    pub fn body(&self) -> &RigidBody {
+        return &self.body;
+    }
+
+    pub fn pos(&self) -> Point {
+        return self.body.pos;
+    }
+
+    pub fn vel(&self) -> Point {
+        return self.body.vel;
+    }
+
+    // The outline in the world
+    pub fn shape(&self) -> &Polygon {
+        return &self.shape;
+    }
+
+    // The narrowest the outline gets, anything moving further than this in one go could pass through it
+    pub fn width(&self) -> f64 {
+        return self.width;
+    }
+
+    fn place(&mut self) {
+        self.shape.place(&self.local, self.body.pos, self.body.angle);
+    }
+
+    pub fn set_transform(&mut self, pos: Point, angle: f64) {
+        self.body.pos = pos;
+        self.body.angle = angle;
+        self.place();
+    }
+
+    pub fn set_vel(&mut self, vel: Point) {
+        self.body.vel = vel;
+    }
+
+    pub fn shift(&mut self, offset: Point) {
+        self.body.pos = self.body.pos + offset;
+        self.place();
+    }
+
+    pub fn integrate(&mut self, delta: f64) {
+        self.body.integrate(delta);
+        self.place();
+    }
+
+    // Solves a contact with another solid, see solve_contact. Only velocities change, so the outlines stay where they are.
+    pub fn contact(&mut self, other: &mut Solid, p: Point, normal: Point) -> f64 {
+        return solve_contact(&mut self.body, &mut other.body, p, normal);
+    }
+
+    // Solves a contact with a body that has no outline, like a wall
+    pub fn contact_body(&mut self, other: &mut RigidBody, p: Point, normal: Point) -> f64 {
+        return solve_contact(&mut self.body, other, p, normal);
+    }
+
+    // How far a point of the outline can move in delta seconds, spin included
+    pub fn travel(&self, delta: f64) -> f64 {
+        return (self.body.vel.len() + self.body.ang_vel.abs() * self.shape.radius) * delta;
+    }
+
+    // Everywhere the outline can be once its centre has moved up to travel
+    pub fn reach(&self, travel: f64) -> Rectangle {
+        return Rectangle::around(self.body.pos, self.shape.radius + travel);
+    }
+
+    // Remember the current transform so rendering can interpolate towards the next one
+    pub fn store_previous(&mut self) {
+        self.prev_pos = self.body.pos;
+        self.prev_angle = self.body.angle;
+    }
+
+    // Where the centre is drawn between the last two steps
+    pub fn render_pos(&self, alpha: f64) -> Point {
+        return self.prev_pos + (self.body.pos - self.prev_pos) * alpha;
+    }
+
+    // The outline between the last two steps, for drawing. Turns the short way round,
+    // an angle that is set rather than integrated can wrap between steps.
+    pub fn outline(&self, alpha: f64) -> Vec<Point> {
+        let mut turn = self.body.angle - self.prev_angle;
+        if turn > PI {
+            turn -= 2.0 * PI;
+        } else if turn < -PI {
+            turn += 2.0 * PI;
+        }
+        let pos = self.render_pos(alpha);
+        let angle = self.prev_angle + turn * alpha;
+        let origin = Point::new(0.0, 0.0);
+        return self.local.points.iter().map(|p| p.rotated(angle, origin) + pos).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        return (a - b).abs() < 1e-9;
+    }
+
+    fn momentum(bodies: &[&RigidBody]) -> (Point, f64) {
+        let mut linear = Point::new(0.0, 0.0);
+        let mut angular = 0.0;
+        for b in bodies {
+            linear = linear + b.vel * b.mass;
+            angular += b.inertia * b.ang_vel + b.mass * cross(b.pos, b.vel);
+        }
+        return (linear, angular);
+    }
+
+    #[test]
+    fn off_centre_contact_conserves_momentum() {
+        let mut a = RigidBody::new(Point::new(0.0, 0.0), Point::new(30.0, 5.0), 2.0, 40.0, 0.5, 0.4);
+        a.ang_vel = 0.3;
+        let mut b = RigidBody::new(Point::new(10.0, 3.0), Point::new(-20.0, 8.0), 3.0, 70.0, 0.2, 0.6);
+        let before = momentum(&[&a, &b]);
+        let j = solve_contact(&mut a, &mut b, Point::new(5.0, -2.0), Point::new(1.0, 0.0));
+        assert!(j < 0.0);
+        let after = momentum(&[&a, &b]);
+        assert!(close(before.0.x, after.0.x) && close(before.0.y, after.0.y));
+        assert!(close(before.1, after.1));
+        // Spin changed, so the impulse was not through the centres
+        assert!(!close(a.ang_vel, 0.3) && !close(b.ang_vel, 0.0));
+    }
+
+    #[test]
+    fn head_on_contact_uses_the_higher_restitution() {
+        let mut a = RigidBody::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 1.0, f64::INFINITY, 1.0, 0.0);
+        let mut b = RigidBody::new(Point::new(10.0, 0.0), Point::new(0.0, 0.0), 1.0, f64::INFINITY, 0.0, 0.0);
+        solve_contact(&mut a, &mut b, Point::new(5.0, 0.0), Point::new(1.0, 0.0));
+        // Equal masses swap velocities in an elastic collision
+        assert!(close(a.vel.x, 0.0) && close(b.vel.x, 10.0));
+    }
+
+    #[test]
+    fn separating_contact_is_left_alone() {
+        let mut a = RigidBody::new(Point::new(0.0, 0.0), Point::new(-10.0, 4.0), 1.0, 5.0, 1.0, 1.0);
+        let mut b = RigidBody::new(Point::new(10.0, 0.0), Point::new(3.0, 0.0), 1.0, 5.0, 1.0, 1.0);
+        assert_eq!(solve_contact(&mut a, &mut b, Point::new(5.0, 1.0), Point::new(1.0, 0.0)), 0.0);
+        assert_eq!(a.vel, Point::new(-10.0, 4.0));
+        assert_eq!(b.vel, Point::new(3.0, 0.0));
+        assert_eq!(a.ang_vel, 0.0);
+        assert_eq!(b.ang_vel, 0.0);
+    }
+
+    #[test]
+    fn fixed_body_does_not_move_and_decides_the_bounce() {
+        let mut wall = RigidBody::fixed(Point::new(0.0, 0.0), 0.9, 0.0);
+        let mut body = RigidBody::new(Point::new(0.0, -10.0), Point::new(5.0, 20.0), 4.0, f64::INFINITY, 0.1, 0.5);
+        // The wall is below the body, the normal points from the body down into it
+        let j = solve_contact(&mut body, &mut wall, Point::new(0.0, 0.0), Point::new(0.0, 1.0));
+        assert!(j < 0.0);
+        assert_eq!(wall.vel, Point::new(0.0, 0.0));
+        assert_eq!(wall.ang_vel, 0.0);
+        assert!(close(body.vel.y, -18.0));
+        // A frictionless wall leaves the sliding speed alone
+        assert!(close(body.vel.x, 5.0));
+    }
+
+    fn square(centre: Point) -> Polygon {
+        let d = [Point::new(-5.0, -5.0), Point::new(5.0, -5.0), Point::new(5.0, 5.0), Point::new(-5.0, 5.0)];
+        return Polygon::new(d.iter().map(|p| *p + centre).collect());
+    }
+
+    #[test]
+    fn outline_follows_the_body() {
+        let mut body = RigidBody::new(Point::new(0.0, 0.0), Point::new(60.0, 0.0), 100.0, 1000.0, 0.5, 0.0);
+        body.ang_vel = 0.5 * PI;
+        let mut solid = Solid::new(square(Point::new(100.0, 50.0)), body);
+        assert_eq!(solid.pos(), Point::new(100.0, 50.0));
+        solid.integrate(1.0);
+        solid.shift(Point::new(0.0, 10.0));
+        let centroid = Polygon::new(solid.shape().points.clone()).centre;
+        assert!(centroid.dist(Point::new(160.0, 60.0)) < 1e-9 && solid.pos().dist(centroid) < 1e-9);
+        // A quarter turn brings a corner of the square back onto a corner
+        assert!(solid.shape().points.iter().any(|p| p.dist(Point::new(155.0, 55.0)) < 1e-9));
+    }
+
+    #[test]
+    fn drawn_outline_turns_the_short_way_round() {
+        let body = RigidBody::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0), 1.0, f64::INFINITY, 0.0, 0.0);
+        let mut solid = Solid::new(square(Point::new(0.0, 0.0)), body);
+        solid.set_transform(Point::new(0.0, 0.0), 0.9 * PI);
+        solid.store_previous();
+        solid.set_transform(Point::new(10.0, 0.0), -0.9 * PI);
+        // Halfway between 0.9 pi and -0.9 pi going through pi, not through 0
+        let expected = Polygon::new(square(Point::new(0.0, 0.0)).points.iter().map(|p| p.rotated(PI, Point::new(0.0, 0.0)) + Point::new(5.0, 0.0)).collect());
+        for (p, q) in solid.outline(0.5).iter().zip(&expected.points) {
+            assert!(p.dist(*q) < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::shapes::Rectangle;
+
+// Bounds are grown by this much, so pairs pushed together while earlier contacts
+// in the same pass are solved are still found. A body pushed further than twice this has to be rechecked by the caller.
+pub const MARGIN: f64 = 4.0;
+
+// Sweep and prune over bounding rectangles. Boxes are sorted by their left edge and swept from left to right,
+// each box is only tested against the boxes that are still open where it starts.
+// The buffers are kept between steps so nothing is allocated once they have grown.
+pub struct BroadPhase {
+    bounds: Vec<Rectangle>,
+    order: Vec<usize>,
+    active: Vec<usize>,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl BroadPhase {
+    pub fn new() -> BroadPhase {
+        BroadPhase { bounds: Vec::new(), order: Vec::new(), active: Vec::new(), pairs: Vec::new() }
+    }
+
+    // Every (i, j) with j < i whose bounds overlap, ordered by i and then j like a nested loop over all pairs
+    pub fn pairs(&mut self, bounds: impl Iterator<Item = Rectangle>) -> &[(usize, usize)] {
+        self.bounds.clear();
+        self.bounds.extend(bounds);
+        let bounds = &self.bounds;
+
+        self.order.clear();
+        self.order.extend(0..bounds.len());
+        self.order.sort_unstable_by(|&a, &b| bounds[a].x.total_cmp(&bounds[b].x));
+
+        self.active.clear();
+        self.pairs.clear();
+        for &i in &self.order {
+            let a = &bounds[i];
+            self.active.retain(|&j| bounds[j].x + bounds[j].w + 2.0 * MARGIN >= a.x);
+            for &j in &self.active {
+                let b = &bounds[j];
+                if a.y <= b.y + b.h + 2.0 * MARGIN && b.y <= a.y + a.h + 2.0 * MARGIN {
+                    self.pairs.push((i.max(j), i.min(j)));
+                }
+            }
+            self.active.push(i);
+        }
+        self.pairs.sort_unstable();
+        return &self.pairs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn brute_force(bounds: &[Rectangle]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..bounds.len() {
+            for j in 0..i {
+                let (a, b) = (&bounds[i], &bounds[j]);
+                if a.x <= b.x + b.w + 2.0 * MARGIN && b.x <= a.x + a.w + 2.0 * MARGIN
+                    && a.y <= b.y + b.h + 2.0 * MARGIN && b.y <= a.y + a.h + 2.0 * MARGIN {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        return pairs;
+    }
+
+    #[test]
+    fn finds_the_same_pairs_as_checking_every_pair() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut broad_phase = BroadPhase::new();
+        for round in 0..50 {
+            let count = rng.gen_range(0..200);
+            // Later rounds crowd the boxes together, some of them without area or touching exactly
+            let size = 10.0 + round as f64 * 4.0;
+            let bounds = (0..count).map(|_| {
+                let w = if rng.gen_bool(0.1) { 0.0 } else { rng.gen::<f64>() * size };
+                let h = rng.gen::<f64>() * size;
+                Rectangle::new(rng.gen::<f64>() * 1000.0, rng.gen::<f64>() * 1000.0, w, h)
+            }).collect::<Vec<_>>();
+            let expected = brute_force(&bounds);
+            assert_eq!(broad_phase.pairs(bounds.iter().copied()), &expected[..]);
+        }
+    }
+
+    #[test]
+    fn boxes_just_outside_the_margin_are_apart() {
+        let mut broad_phase = BroadPhase::new();
+        let a = Rectangle::new(0.0, 0.0, 10.0, 10.0);
+        let touching = Rectangle::new(10.0 + 2.0 * MARGIN, 0.0, 10.0, 10.0);
+        let apart = Rectangle::new(10.0 + 2.0 * MARGIN + 0.01, 0.0, 10.0, 10.0);
+        assert_eq!(broad_phase.pairs([a, touching].into_iter()), &[(1, 0)]);
+        assert!(broad_phase.pairs([a, apart].into_iter()).is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::shapes::Point;
+use crate::{ARENA_WIDTH, ARENA_HEIGHT, FIELD_WIDTH, FIELD_HEIGHT};
+
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 2.0;
+// Fraction of the distance to the target covered per second, roughly
+const FOLLOW_RATE: f64 = 6.0;
+// Seconds of player velocity the camera looks ahead by
+const LEAD: f64 = 0.3;
+
+// Which part of the arena is on screen. The screen is the FIELD_WIDTH x FIELD_HEIGHT playfield
+// from Viewport, the camera centre is shown in its middle and zoom is screen units per world unit.
+pub struct Camera {
+    pub centre: Point,
+    pub zoom: f64,
+}
+
+impl Camera {
+    pub fn new(centre: Point) -> Camera {
+        let mut camera = Camera { centre, zoom: 1.0 };
+        camera.clamp();
+        return camera;
+    }
+
+    // Half the size of the visible area in world units
+    pub fn half_view(&self) -> Point {
+        return Point::new(FIELD_WIDTH, FIELD_HEIGHT) / (2.0 * self.zoom);
+    }
+
+    // Keeps the view inside the arena, or centred on it when zoomed out past its size
+    fn clamp(&mut self) {
+        let half = self.half_view();
+        self.centre.x = if 2.0 * half.x >= ARENA_WIDTH { ARENA_WIDTH / 2.0 } else { self.centre.x.clamp(half.x, ARENA_WIDTH - half.x) };
+        self.centre.y = if 2.0 * half.y >= ARENA_HEIGHT { ARENA_HEIGHT / 2.0 } else { self.centre.y.clamp(half.y, ARENA_HEIGHT - half.y) };
+    }
+
+    // Eases towards a point a little ahead of where the target is moving
+    pub fn follow(&mut self, target: Point, vel: Point, delta: f64) {
+        let goal = target + vel * LEAD;
+        self.centre = self.centre + (goal - self.centre) * (1.0 - (-FOLLOW_RATE * delta).exp());
+        self.clamp();
+    }
+
+    pub fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.clamp();
+    }
+
+    pub fn to_screen(&self, p: Point) -> Point {
+        return (p - self.centre) * self.zoom + Point::new(FIELD_WIDTH, FIELD_HEIGHT) / 2.0;
+    }
+
+    pub fn to_world(&self, p: Point) -> Point {
+        return (p - Point::new(FIELD_WIDTH, FIELD_HEIGHT) / 2.0) / self.zoom + self.centre;
+    }
+
+    // Whether a circle in world coordinates overlaps the screen
+    pub fn visible(&self, p: Point, radius: f64) -> bool {
+        let half = self.half_view();
+        return (p.x - self.centre.x).abs() < half.x + radius && (p.y - self.centre.y).abs() < half.y + radius;
+    }
+}
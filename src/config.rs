@@ -0,0 +1,30 @@
+pub struct Entry {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+// Reads `key = value` lines. Blank lines and everything after a '#' are ignored.
+// first_line is the line number of the text's first line in source, for text cut out of a larger file.
+pub fn parse_entries(text: &str, source: &str, first_line: usize) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (ix, line) in text.lines().enumerate() {
+        let number = first_line + ix;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(format!("{}:{}: expected 'key = value', got '{}'", source, number, line))?;
+        entries.push(Entry { line: number, key: key.trim().to_string(), value: value.trim().to_string() });
+    }
+    return Ok(entries);
+}
+
+// Reads a config file, a missing file is only an error if the user asked for it explicitly
+pub fn read_config(path: &str, required: bool) -> Result<Option<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Ok(None),
+        Err(e) => Err(format!("Could not read '{}': {}", path, e))
+    }
+}
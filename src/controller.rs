@@ -0,0 +1,100 @@
+use sdl2::controller::{Axis, GameController};
+use sdl2::GameControllerSubsystem;
+use crate::shapes::Point;
+use crate::world::InputEvent;
+
+const DEAD_ZONE: f64 = 0.2;
+const TRIGGER_THRESHOLD: f64 = 0.5;
+// Smaller stick changes are not sent on, so jitter neither floods the input nor the replay
+const MIN_CHANGE: f64 = 0.05;
+
+pub enum PadEvent {
+    Input(InputEvent),
+    Trigger(Axis, bool),
+}
+
+// Keeps connected controllers open and turns their stick motion into world input
+pub struct Controllers {
+    subsystem: Option<GameControllerSubsystem>,
+    open: Vec<GameController>,
+    left: Point,
+    right: Point,
+    // The stick positions last sent to the world
+    sent_left: Point,
+    sent_right: Point,
+    triggers: [bool; 2], // [left, right]
+}
+
+fn normalize(value: i16) -> f64 {
+    let v = (value as f64 / 32767.0).clamp(-1.0, 1.0);
+    if v.abs() < DEAD_ZONE {
+        return 0.0;
+    }
+    return v.signum() * (v.abs() - DEAD_ZONE) / (1.0 - DEAD_ZONE);
+}
+
+impl Controllers {
+    // Without the subsystem no controllers are ever opened
+    pub fn new(subsystem: Option<GameControllerSubsystem>) -> Controllers {
+        let zero = Point::new(0.0, 0.0);
+        Controllers { subsystem, open: Vec::new(), left: zero, right: zero, sent_left: zero, sent_right: zero, triggers: [false; 2] }
+    }
+
+    pub fn device_added(&mut self, index: u32) {
+        if let Some(subsystem) = &self.subsystem {
+            match subsystem.open(index) {
+                Ok(controller) => self.open.push(controller),
+                Err(e) => eprintln!("Could not open controller {}: {}", index, e)
+            }
+        }
+    }
+
+    pub fn device_removed(&mut self, instance_id: u32) {
+        self.open.retain(|c| c.instance_id() != instance_id);
+    }
+
+    pub fn axis_motion(&mut self, axis: Axis, value: i16) -> Vec<PadEvent> {
+        let v = normalize(value);
+        match axis {
+            Axis::LeftX | Axis::LeftY => {
+                if axis == Axis::LeftX {
+                    self.left.x = v;
+                } else {
+                    self.left.y = v;
+                }
+                // Returning to the centre is always sent, so the ship never keeps drifting
+                let centred = self.left.len() == 0.0;
+                if self.left.dist(self.sent_left) >= MIN_CHANGE || (centred && self.sent_left.len() > 0.0) {
+                    self.sent_left = self.left;
+                    return vec![PadEvent::Input(InputEvent::Stick(self.left))];
+                }
+                return vec![];
+            },
+            Axis::RightX | Axis::RightY => {
+                if axis == Axis::RightX {
+                    self.right.x = v;
+                } else {
+                    self.right.y = v;
+                }
+                // A released stick keeps the last aim
+                if self.right.len() > 0.0 {
+                    let dir = self.right / self.right.len();
+                    if dir.dist(self.sent_right) >= MIN_CHANGE {
+                        self.sent_right = dir;
+                        return vec![PadEvent::Input(InputEvent::Aim(dir))];
+                    }
+                }
+                return vec![];
+            },
+            Axis::TriggerLeft | Axis::TriggerRight => {
+                let ix = if axis == Axis::TriggerLeft { 0 } else { 1 };
+                let pressed = v > TRIGGER_THRESHOLD;
+                if pressed != self.triggers[ix] {
+                    self.triggers[ix] = pressed;
+                    return vec![PadEvent::Trigger(axis, pressed)];
+                }
+                return vec![];
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::shapes::Point;
+
+// Seconds a laser ray stays on the overlay, a single step would be too short to see
+pub const RAY_TIME: f64 = 0.5;
+
+// A contact from get_collision as it was when the collision was solved
+pub struct Contact {
+    pub point: Point,
+    pub shift: Point,
+    pub normal: Point,
+}
+
+// Physics details gathered by the world while stepping, so the overlay can draw them once per frame.
+// Nothing is recorded while disabled and none of it feeds back into the simulation.
+pub struct DebugInfo {
+    pub enabled: bool,
+    pub contacts: Vec<Contact>,
+    // Start, end and the time left to show it
+    pub rays: Vec<(Point, Point, f64)>,
+}
+
+impl DebugInfo {
+    pub fn new() -> DebugInfo {
+        DebugInfo { enabled: false, contacts: Vec::new(), rays: Vec::new() }
+    }
+
+    // Called at the start of every step. Contacts only show the latest step, rays fade out over RAY_TIME.
+    pub fn begin_step(&mut self, delta: f64) {
+        self.contacts.clear();
+        for ray in &mut self.rays {
+            ray.2 -= delta;
+        }
+        self.rays.retain(|ray| ray.2 > 0.0);
+    }
+
+    pub fn contact(&mut self, point: Point, shift: Point, normal: Point) {
+        if self.enabled {
+            self.contacts.push(Contact { point, shift, normal });
+        }
+    }
+
+    pub fn ray(&mut self, start: Point, end: Point) {
+        if self.enabled {
+            self.rays.push((start, end, RAY_TIME));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::asteroid::Asteroid;
+use crate::camera::Camera;
+use crate::player::Player;
+use crate::shapes::Point;
+
+fn polygon<T: RenderTarget>(canvas: &mut Canvas<T>, camera: &Camera, points: &[Point], color: Color) -> Result<(), String> {
+    let points = points.iter().map(|p| camera.to_screen(*p)).collect::<Vec<_>>();
+    let vx = points.iter().map(|p| p.x as i16).collect::<Vec<_>>();
+    let vy = points.iter().map(|p| p.y as i16).collect::<Vec<_>>();
+    return canvas.aa_polygon(&vx, &vy, color);
+}
+
+pub fn asteroid<T: RenderTarget>(canvas: &mut Canvas<T>, camera: &Camera, asteroid: &Asteroid, alpha: f64) -> Result<(), String> {
+    if asteroid.shape().corners() == 0 || !camera.visible(asteroid.shape().centre, asteroid.shape().radius) {
+        return Ok(());
+    }
+    return polygon(canvas, camera, &asteroid.outline(alpha), Color::RGB(0xff, 0xff, 0xff));
+}
+
+pub fn player<T: RenderTarget>(canvas: &mut Canvas<T>, camera: &Camera, player: &Player, alpha: f64) -> Result<(), String> {
+    if player.shape().corners() == 0 {
+        return Ok(());
+    }
+    // Blink while invulnerable
+    if player.invulnerable() && (player.invulnerable_time() * 8.0) as i64 % 2 == 1 {
+        return Ok(());
+    }
+
+    polygon(canvas, camera, &player.outline(alpha), Color::RGB(0xff, 0x00, 0x00))?;
+
+    // The emitter is drawn where the beam starts while firing, on the ship otherwise
+    let firing = player.cooldown() > 0.0;
+    let (start, end) = player.laser_ray();
+    let origin = if firing { start } else { player.render_pos(alpha) };
+    let centre = Point::new(0.0, 0.0);
+    let emitter = [Point::new(-10.0, 10.0), Point::new(-10.0, -10.0), Point::new(10.0, -10.0), Point::new(10.0, 10.0)]
+        .map(|p| origin + p.rotated(player.laser_facing(), centre));
+    polygon(canvas, camera, &emitter, Color::RGB(0xf0, 0xf0, 0xf0))?;
+    if firing {
+        let start = camera.to_screen(start);
+        let end = camera.to_screen(end);
+        canvas.aa_line(start.x as i16, start.y as i16, end.x as i16, end.y as i16, Color::RGB(0xff, 0x00, 0x00))?;
+    }
+    return Ok(());
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "meteoroids-highscores 1";
+pub const MAX_ENTRIES: usize = 10;
+pub const MAX_NAME_LEN: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub date: u64, // seconds since the unix epoch
+    pub time: f64,
+    pub score: u64,
+    pub seed: u64,
+}
+
+impl Entry {
+    pub fn new(name: &str, time: f64, score: u64, seed: u64) -> Entry {
+        let date = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Entry { name: clean_name(name), date, time, score, seed }
+    }
+
+    fn ranks_above(&self, time: f64, score: u64) -> bool {
+        return self.score > score || (self.score == score && self.time >= time);
+    }
+
+    fn parse(line: &str) -> Option<Entry> {
+        let parts = line.split('\t').collect::<Vec<_>>();
+        if let [name, date, time, score, seed] = parts.as_slice() {
+            let time = time.parse::<f64>().ok().filter(|t| t.is_finite() && *t >= 0.0)?;
+            return Some(Entry {
+                name: clean_name(name),
+                date: date.parse().ok()?,
+                time,
+                score: score.parse().ok()?,
+                seed: seed.parse().ok()?,
+            });
+        }
+        return None;
+    }
+}
+
+// Names are stored tab separated on one line
+fn clean_name(name: &str) -> String {
+    return name.chars().filter(|c| !c.is_control()).take(MAX_NAME_LEN).collect::<String>().trim().to_string();
+}
+
+// YYYY-MM-DD in UTC
+pub fn format_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    // Days to civil date, from Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    return format!("{:04}-{:02}-{:02}", y, m, d);
+}
+
+pub struct HighScores {
+    path: Option<PathBuf>,
+    pub entries: Vec<Entry>,
+}
+
+impl HighScores {
+    // Never fails, a missing, unreadable or foreign file just gives an empty table
+    // and broken lines are skipped.
+    pub fn load(path: Option<PathBuf>) -> HighScores {
+        let mut scores = HighScores { path, entries: Vec::new() };
+        let text = match scores.path.as_ref().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            _ => return scores
+        };
+        let mut lines = text.lines();
+        if lines.next().map(|l| l.trim()) != Some(HEADER) {
+            eprintln!("Ignoring high score file in an unknown format");
+            return scores;
+        }
+        for line in lines {
+            match Entry::parse(line) {
+                Some(entry) => {
+                    scores.insert(entry);
+                },
+                None => eprintln!("Skipping broken high score line '{}'", line)
+            }
+        }
+        return scores;
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(())
+        };
+        let mut out = String::new();
+        out.push_str(HEADER);
+        out.push('\n');
+        for e in &self.entries {
+            out.push_str(&format!("{}\t{}\t{}\t{}\t{}\n", e.name, e.date, e.time, e.score, e.seed));
+        }
+        // Written next to the table and renamed over it, so a crash while writing cannot leave half a table behind
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, out).map_err(|e| format!("Could not write high scores to '{}': {}", temp.display(), e))?;
+        return fs::rename(&temp, path).map_err(|e| format!("Could not replace high scores in '{}': {}", path.display(), e));
+    }
+
+    // Whether the table is kept in a file, new entries are lost otherwise
+    pub fn saved(&self) -> bool {
+        return self.path.is_some();
+    }
+
+    pub fn qualifies(&self, time: f64, score: u64) -> bool {
+        return self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|e| !e.ranks_above(time, score));
+    }
+
+    // Returns the rank the entry got, or None if it did not make the table
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self.entries.iter().position(|e| !e.ranks_above(entry.time, entry.score)).unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        return Some(rank);
+    }
+
+    // Suggested name for a new entry, whoever set the latest score
+    pub fn last_name(&self) -> String {
+        return self.entries.iter().max_by_key(|e| e.date).map(|e| e.name.clone()).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A file under the temp directory that is removed again when the test ends
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> TempFile {
+            let path = std::env::temp_dir().join(format!("meteoroids-test-{}-{}", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            return TempFile(path);
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            let _ = fs::remove_file(self.0.with_extension("tmp"));
+        }
+    }
+
+    #[test]
+    fn missing_file_gives_an_empty_table() {
+        let scores = HighScores::load(Some(std::env::temp_dir().join("meteoroids-test-does-not-exist")));
+        assert!(scores.entries.is_empty());
+        assert!(HighScores::load(None).entries.is_empty());
+    }
+
+    #[test]
+    fn corrupt_and_old_files_load_empty() {
+        let binary = TempFile::new("binary", &[0xff, 0xfe, 0x00, 0x80, 0x0a]);
+        assert!(HighScores::load(Some(binary.0.clone())).entries.is_empty());
+
+        let old = TempFile::new("old", b"meteoroids-highscores 0\nbob\t1\t2\t3\t4\n");
+        assert!(HighScores::load(Some(old.0.clone())).entries.is_empty());
+
+        let empty = TempFile::new("empty", b"");
+        assert!(HighScores::load(Some(empty.0.clone())).entries.is_empty());
+    }
+
+    #[test]
+    fn broken_lines_are_skipped() {
+        let text = format!("{}\nann\t100\t12.5\t300\t7\nnot a score\nbob\tx\t1\t2\t3\ncid\t100\t-1\t5\t9\ndee\t100\t3.5\t200\t8\n", HEADER);
+        let file = TempFile::new("broken", text.as_bytes());
+        let scores = HighScores::load(Some(file.0.clone()));
+        assert_eq!(scores.entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["ann", "dee"]);
+    }
+
+    #[test]
+    fn saved_table_loads_back() {
+        let file = TempFile::new("roundtrip", b"");
+        let mut scores = HighScores::load(Some(file.0.clone()));
+        scores.insert(Entry::new("ann", 10.0, 500, 1));
+        scores.insert(Entry::new("bob\tby", 20.0, 900, 2));
+        scores.save().unwrap();
+        assert!(!file.0.with_extension("tmp").exists());
+
+        let loaded = HighScores::load(Some(file.0.clone()));
+        assert_eq!(loaded.entries.iter().map(|e| (e.name.as_str(), e.score)).collect::<Vec<_>>(), [("bobby", 900), ("ann", 500)]);
+    }
+
+    #[test]
+    fn table_without_a_file_is_not_saved() {
+        let mut scores = HighScores::load(None);
+        assert!(!scores.saved());
+        // Anything qualifies for an empty table, the game has to check saved() before asking for a name
+        assert!(scores.qualifies(1.0, 1));
+        scores.insert(Entry::new("ann", 10.0, 500, 1));
+        assert!(scores.save().is_ok());
+        assert!(HighScores::load(Some(std::env::temp_dir().join("meteoroids-test-never-written"))).saved());
+    }
+}
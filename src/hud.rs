@@ -0,0 +1,39 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::text::{Align, Style, Text};
+use crate::world::World;
+use crate::FIELD_WIDTH;
+
+const MARGIN: f64 = 20.0;
+
+// Time, score, lives and laser state drawn over the playfield while a run is going
+pub fn render<T: RenderTarget>(canvas: &mut Canvas<T>, text: &Text, world: &World) -> Result<(), String> {
+    let white = Color::RGB(0xff, 0xff, 0xff);
+    let grey = Color::RGB(0x80, 0x80, 0x80);
+
+    text.draw(canvas, MARGIN, MARGIN, &format!("{:.1}", world.time_alive), Style::new(white, 32.0).shadow())?;
+    text.draw(canvas, MARGIN, MARGIN + 36.0, &format!("seed {}", world.seed), Style::new(grey, 14.0))?;
+
+    text.draw(canvas, FIELD_WIDTH - MARGIN, MARGIN, &world.score.points.to_string(), Style::new(white, 32.0).align(Align::Right).shadow())?;
+    if world.score.combo > 1.0 {
+        let combo = Style::new(Color::RGB(0xff, 0xd0, 0x40), 20.0).align(Align::Right).shadow();
+        text.draw(canvas, FIELD_WIDTH - MARGIN, MARGIN + 36.0, &format!("x{:.1} combo", world.score.combo), combo)?;
+    }
+
+    // One small ship per life
+    for i in 0..world.lives as i16 {
+        let x = MARGIN as i16 + 24 * i;
+        let y = MARGIN as i16 + 58;
+        canvas.aa_trigon(x, y + 18, x + 8, y, x + 16, y + 18, Color::RGB(0xff, 0x00, 0x00))?;
+    }
+
+    let cooldown = world.player.cooldown() / world.tuning.firing_time;
+    let (laser, color) = if cooldown > 0.0 {
+        (format!("LASER {:>3}%", ((1.0 - cooldown) * 100.0) as i32), grey)
+    } else {
+        ("LASER READY".to_string(), white)
+    };
+    text.draw(canvas, MARGIN, MARGIN + 84.0, &laser, Style::new(color, 16.0).shadow())?;
+    return Ok(());
+}
@@ -0,0 +1,70 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::asteroid::Asteroid;
+use crate::camera::Camera;
+use crate::shapes::Point;
+use crate::world::World;
+use crate::{FIELD_WIDTH, FIELD_HEIGHT};
+
+// Asteroids further out than this many seconds get no warning
+const WARNING_TIME: f64 = 5.0;
+// Distance of the arrows from the screen edge
+const INSET: f64 = 24.0;
+const MIN_SIZE: f64 = 8.0;
+const MAX_SIZE: f64 = 28.0;
+
+// Seconds until an off-screen asteroid's outline reaches the visible area, if it is heading into it.
+// The view is grown by the asteroid's radius and the centre's path is clipped against it one axis at a time.
+fn time_to_entry(asteroid: &Asteroid, camera: &Camera) -> Option<f64> {
+    let half = camera.half_view() + Point::new(asteroid.shape().radius, asteroid.shape().radius);
+    let pos = asteroid.shape().centre - camera.centre;
+    let vel = asteroid.vel();
+    let mut enter = 0.0_f64;
+    let mut exit = f64::MAX;
+    for (p, v, h) in [(pos.x, vel.x, half.x), (pos.y, vel.y, half.y)] {
+        if v == 0.0 {
+            if p.abs() > h {
+                return None;
+            }
+            continue;
+        }
+        let (t1, t2) = ((-h - p) / v, (h - p) / v);
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+    }
+    if enter > exit || exit <= 0.0 {
+        return None;
+    }
+    return Some(enter);
+}
+
+// An arrow at the screen edge for each asteroid about to come into view, pointing the way it moves.
+// Heavier asteroids get bigger arrows, and the arrows fade in as the asteroid gets closer to entering.
+pub fn render<T: RenderTarget>(canvas: &mut Canvas<T>, world: &World, camera: &Camera) -> Result<(), String> {
+    for a in &world.asteroids {
+        if camera.visible(a.shape().centre, a.shape().radius) {
+            continue;
+        }
+        let t = match time_to_entry(a, camera) {
+            Some(t) if t <= WARNING_TIME => t,
+            _ => continue
+        };
+        let vel = a.vel();
+        let dir = vel / vel.dist(Point::new(0.0, 0.0));
+        let side = Point::new(-dir.y, dir.x);
+
+        let entry = camera.to_screen(a.shape().centre + vel * t);
+        let p = Point::new(entry.x.clamp(INSET, FIELD_WIDTH - INSET), entry.y.clamp(INSET, FIELD_HEIGHT - INSET));
+        let size = (a.mass().sqrt() * 0.15).clamp(MIN_SIZE, MAX_SIZE);
+        let tip = p + dir * size;
+        let left = p - dir * (size * 0.6) + side * (size * 0.6);
+        let right = p - dir * (size * 0.6) - side * (size * 0.6);
+
+        let alpha = (255.0 * (1.0 - t / WARNING_TIME)).clamp(40.0, 255.0) as u8;
+        let color = Color::RGBA(0xff, 0x80, 0x20, alpha);
+        canvas.filled_trigon(tip.x as i16, tip.y as i16, left.x as i16, left.y as i16, right.x as i16, right.y as i16, color)?;
+        canvas.aa_trigon(tip.x as i16, tip.y as i16, left.x as i16, left.y as i16, right.x as i16, right.y as i16, color)?;
+    }
+    return Ok(());
+}
@@ -0,0 +1,18 @@
+// Explicit returns and plain `new` constructors are the house style
+#![allow(clippy::needless_return, clippy::new_without_default)]
+
+// The simulation without any SDL dependency, so tests, bots and tools can drive a World directly.
+// The game in main.rs adds input, rendering and sound on top of it.
+pub mod asteroid;
+pub mod body;
+pub mod broadphase;
+pub mod config;
+pub mod debug;
+pub mod highscore;
+pub mod player;
+pub mod replay;
+pub mod score;
+pub mod shapes;
+pub mod timestep;
+pub mod tuning;
+pub mod world;
@@ -1,200 +1,324 @@
+// Explicit returns and plain `new` constructors are the house style
+#![allow(clippy::needless_return, clippy::new_without_default)]
+
 extern crate sdl2;
 
-use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
-use sdl2::event::Event;
-use sdl2::render::{Canvas, RenderTarget};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-mod shapes;
-use shapes::{Point, Polygon};
-mod player;
-use player::Player;
-mod asteroid;
-use asteroid::Asteroid;
+use asteroids::{asteroid, config, debug, highscore, player, replay, shapes, timestep, tuning, world};
+use asteroids::world::{ARENA_WIDTH, ARENA_HEIGHT};
+use shapes::Point;
+use world::{InputState, World};
+use timestep::FixedTimestep;
+mod options;
+use options::Options;
+use replay::{Playback, Replay};
+mod bindings;
+use bindings::{Action, Binding, Bindings};
+mod controller;
+use controller::{Controllers, PadEvent};
+use tuning::Tuning;
+mod viewport;
+use viewport::Viewport;
+mod state;
+use state::{Frame, GameState};
+use highscore::HighScores;
+mod text;
+use text::{Align, Style, Text};
+mod hud;
+mod audio;
+use audio::Audio;
+mod music;
+mod background;
+use background::Background;
+mod particles;
+use particles::Particles;
+mod camera;
+use camera::Camera;
+mod minimap;
+mod indicators;
+mod draw;
+mod overlay;
 
-const WINDOW_WIDTH: f64 = 1600.0;
-const WINDOW_HEIGHT: f64 = 900.0;
-const TIME_BETWEEN_METEOROIDS: f64 = 30.0;
+const FIELD_WIDTH: f64 = 1600.0;
+const FIELD_HEIGHT: f64 = 900.0;
+const VOLUME_STEP: f64 = 0.1;
+// Zoom factor for one key press or mouse wheel notch
+const ZOOM_STEP: f64 = 1.1;
+// Seconds the volume levels stay on screen after a change
+const VOLUME_NOTICE_TIME: f64 = 2.0;
 
+// The table lives in SDL's per user data directory, without one scores only last for the session
+fn highscore_path() -> Option<PathBuf> {
+    return sdl2::filesystem::pref_path("Paladinking", "Meteoroids").ok().map(|p| PathBuf::from(p).join("highscores.txt"));
+}
 
 pub fn main() {
+    let options = match Options::from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let bindings = Bindings::load(options.bindings.as_deref().unwrap_or(bindings::DEFAULT_PATH), options.bindings.is_some())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+    let mut tuning = Tuning::load(options.tuning.as_deref().unwrap_or(tuning::DEFAULT_PATH), options.tuning.is_some())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
-    let mut time = Instant::now();
 
-    let window = video_subsystem.window("Meteoroids", WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32)
+    let window = video_subsystem.window("Meteoroids", FIELD_WIDTH as u32, FIELD_HEIGHT as u32)
         .position_centered()
+        .resizable()
         .build()
         .unwrap();
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+    let audio_subsystem = sdl_context.audio().map_err(|e| eprintln!("Could not initialise audio: {}", e)).ok();
+    let mut audio = Audio::new(audio_subsystem.as_ref(), options.music_volume, options.effects_volume);
+
+    let texture_creator = canvas.texture_creator();
+    let ttf = sdl2::ttf::init().map_err(|e| eprintln!("Could not initialise SDL_ttf: {}", e)).ok();
+    let text = Text::new(ttf.as_ref(), &texture_creator, options.font.as_deref());
+    let _image = sdl2::image::init(sdl2::image::InitFlag::PNG).map_err(|e| eprintln!("Could not initialise SDL_image: {}", e)).ok();
+    let mut background = Background::new(&texture_creator);
+    let mut particles = Particles::new();
+
+    let (w, h) = canvas.output_size().unwrap();
+    let mut viewport = Viewport::new(w, h);
+    viewport.apply(&mut canvas).unwrap();
 
     canvas.set_draw_color(Color::RGB(0x00, 0x00, 0x00));
     canvas.clear();
     canvas.present();
 
-    let mut reset_game = false;
-    let mut asteroids = vec![];
-    let mut spawn_time = TIME_BETWEEN_METEOROIDS;
-    let mut meteoroid_spawner = Point::new(WINDOW_WIDTH / 2.0, -WINDOW_WIDTH * 1.0);
-    let screen_centre = Point::new(WINDOW_WIDTH / 2.0, WINDOW_HEIGHT / 2.0);
-    let start_meteoroids = 5;
-    for _i in 0..start_meteoroids {
-        let vel = Point::new(screen_centre.x - meteoroid_spawner.x, screen_centre.y - meteoroid_spawner.y) / ((2.0 + rand::random::<f64>()) * 5.0);
-        // print!("start pos: {}x, {}y, start vel: {}x, {}y\n", meteoroid_spawner.x, meteoroid_spawner.y, vel.x, vel.y);
-        let new_asteroid = Asteroid::get_randomized(75.0, meteoroid_spawner, vel);
-        asteroids.push(new_asteroid);
-        meteoroid_spawner = meteoroid_spawner.rotated(2.0 * 3.1415 / start_meteoroids as f64, screen_centre);
+    let mut playback = None;
+    let mut tick_rate = options.tick_rate;
+    let mut seed = options.seed.unwrap_or_else(rand::random);
+    if let Some(path) = &options.replay {
+        let replay = Replay::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        tick_rate = replay.tick_rate;
+        seed = replay.seed;
+        tuning = replay.tuning.clone();
+        playback = Some(Playback::new(replay));
+    }
+    let mut recording = options.record.as_ref().map(|_| Replay::new(seed, tick_rate, tuning.clone()));
+
+    let mut world = World::new(seed, tuning);
+    if let Some(count) = options.stress {
+        world.spawn_stress(count);
     }
+    let mut camera = Camera::new(world.player.pos());
+    let mut input = InputState::new();
+    let mut timestep = FixedTimestep::new(tick_rate, options.max_catch_up);
 
-    let mut player_alive = true;
-    let mut time_alive = 0.0;
-    let mut player = Player::new(screen_centre.clone());
+    let mut controllers = Controllers::new(sdl_context.game_controller().map_err(|e| eprintln!("Could not initialise controllers: {}", e)).ok());
+    // Replays do not count towards the high score table
+    let mut scores = HighScores::load(if playback.is_none() { highscore_path() } else { None });
+    let mut state = if playback.is_some() { GameState::Playing } else { GameState::Title };
+    let mut mouse_pos = Point::new(0.0, 0.0);
+    let mut volume_notice = 0.0;
+    let mut frame_time = 0.0;
 
+    let controls = bindings.controls_hint();
     let mut event_pump = sdl_context.event_pump().unwrap();
+    let mut time = Instant::now();
     'running: loop {
-        let delta = time.elapsed().as_secs_f64();
-
-        if reset_game {
-            reset_game = false;
-            asteroids = vec![];
-            spawn_time = TIME_BETWEEN_METEOROIDS;
-            meteoroid_spawner = Point::new(WINDOW_WIDTH / 2.0, -WINDOW_WIDTH * 1.0);
-            for _i in 0..start_meteoroids {
-                let vel = Point::new(screen_centre.x - meteoroid_spawner.x, screen_centre.y - meteoroid_spawner.y) / ((2.0 + rand::random::<f64>()) * 5.0);
-                // print!("start pos: {}x, {}y, start vel: {}x, {}y\n", meteoroid_spawner.x, meteoroid_spawner.y, vel.x, vel.y);
-                let new_asteroid = Asteroid::get_randomized(75.0, meteoroid_spawner, vel);
-                asteroids.push(new_asteroid);
-                meteoroid_spawner = meteoroid_spawner.rotated(2.0 * 3.1415 / start_meteoroids as f64, screen_centre);
-            }
-            player_alive = true;
-            time_alive = 0.0;
-            player = Player::new(screen_centre.clone());
-        }
+        let now = Instant::now();
+        let elapsed = (now - time).as_secs_f64();
+        time = now;
 
         for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit {..} |
-                Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                    break 'running
+            // Typing a name takes over the keyboard, only releasing a movement key still goes through so the ship stops
+            if let GameState::NameEntry { .. } = state {
+                match event {
+                    Event::TextInput { ref text, .. } => {
+                        state.type_text(text);
+                        continue;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => {
+                        state.backspace();
+                        continue;
+                    },
+                    Event::KeyDown { keycode: Some(Keycode::Return | Keycode::KpEnter), .. } => {
+                        state = state.confirm_name(&world, &mut scores);
+                        continue;
+                    },
+                    Event::KeyDown { .. } => continue,
+                    Event::KeyUp { keycode, scancode, .. } if !bindings.key_action(keycode, scancode).is_some_and(|a| a.is_movement()) => continue,
+                    _ => {}
+                }
+            }
+
+            let mut events = Vec::new();
+            let mut target = Some(camera.to_world(mouse_pos));
+            let (action, pressed) = match event {
+                Event::Quit {..} => break 'running,
+                Event::KeyDown { keycode, scancode, repeat: false, .. } => (bindings.key_action(keycode, scancode), true),
+                Event::KeyUp { keycode, scancode, .. } => (bindings.key_action(keycode, scancode), false),
+                Event::Window { win_event: WindowEvent::FocusLost, .. } => {
+                    state = state.focus_lost();
+                    continue;
                 },
-                Event::KeyDown { keycode: Some(Keycode::R), .. } => {
-                    reset_game = true;
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    let (w, h) = canvas.output_size().unwrap();
+                    viewport = Viewport::new(w, h);
+                    viewport.apply(&mut canvas).unwrap();
+                    continue;
                 },
-                Event::MouseButtonDown { x, y, .. } => {
-                    if player_alive {
-                        player.fire_if_ready(Point{ x: x as f64, y: y as f64 }, &mut asteroids);
-                    }
+                Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+                    mouse_pos = viewport.to_field(x, y);
+                    target = Some(camera.to_world(mouse_pos));
+                    (bindings.mouse_action(mouse_btn), true)
                 },
-                Event::KeyDown { keycode: Some(Keycode::W), .. } => {
-                    player.set_mov_dir(0, true);
+                Event::MouseButtonUp { mouse_btn, .. } => (bindings.mouse_action(mouse_btn), false),
+                Event::MouseMotion { x, y, .. } => {
+                    mouse_pos = viewport.to_field(x, y);
+                    continue;
                 },
-                Event::KeyUp { keycode: Some(Keycode::W), .. } => {
-                    player.set_mov_dir(0, false);
+                Event::MouseWheel { y, .. } => {
+                    camera.zoom_by(ZOOM_STEP.powi(y));
+                    continue;
                 },
-                Event::KeyDown { keycode: Some(Keycode::S), .. } => {
-                    player.set_mov_dir(1, true);
+                Event::ControllerDeviceAdded { which, .. } => {
+                    controllers.device_added(which);
+                    continue;
                 },
-                Event::KeyUp { keycode: Some(Keycode::S), .. } => {
-                    player.set_mov_dir(1, false);
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    controllers.device_removed(which);
+                    continue;
                 },
-                Event::KeyDown { keycode: Some(Keycode::A), .. } => {
-                    player.set_mov_dir(2, true);
+                Event::ControllerButtonDown { button, .. } => {
+                    target = None;
+                    (bindings.action(Binding::Pad(button)), true)
                 },
-                Event::KeyUp { keycode: Some(Keycode::A), .. } => {
-                    player.set_mov_dir(2, false);
+                Event::ControllerButtonUp { button, .. } => {
+                    target = None;
+                    (bindings.action(Binding::Pad(button)), false)
                 },
-                Event::KeyDown { keycode: Some(Keycode::D), .. } => {
-                    player.set_mov_dir(3, true);
+                Event::ControllerAxisMotion { axis, value, .. } => {
+                    target = None;
+                    let mut trigger = (None, false);
+                    for e in controllers.axis_motion(axis, value) {
+                        match e {
+                            PadEvent::Input(e) => events.push(e),
+                            PadEvent::Trigger(axis, pressed) => trigger = (bindings.action(Binding::Trigger(axis)), pressed)
+                        }
+                    }
+                    trigger
                 },
-                Event::KeyUp { keycode: Some(Keycode::D), .. } => {
-                    player.set_mov_dir(3, false);
+                _ => continue
+            };
+            match action {
+                Some(Action::Quit) if pressed => break 'running,
+                Some(Action::Quit) => {},
+                Some(action @ (Action::MusicDown | Action::MusicUp | Action::EffectsDown | Action::EffectsUp)) if pressed => {
+                    match action {
+                        Action::MusicDown => audio.change_music_volume(-VOLUME_STEP),
+                        Action::MusicUp => audio.change_music_volume(VOLUME_STEP),
+                        Action::EffectsDown => audio.change_effects_volume(-VOLUME_STEP),
+                        _ => audio.change_effects_volume(VOLUME_STEP),
+                    }
+                    volume_notice = VOLUME_NOTICE_TIME;
+                },
+                Some(Action::MusicDown | Action::MusicUp | Action::EffectsDown | Action::EffectsUp) => {},
+                Some(Action::ZoomIn) if pressed => camera.zoom_by(ZOOM_STEP),
+                Some(Action::ZoomOut) if pressed => camera.zoom_by(1.0 / ZOOM_STEP),
+                Some(Action::ZoomIn | Action::ZoomOut) => {},
+                Some(Action::Debug) if pressed => world.debug.enabled = !world.debug.enabled,
+                Some(Action::Debug) => {},
+                // A replay can only be paused, everything else comes from the file
+                Some(action) if playback.is_none() || action == Action::Pause => {
+                    let (next, event) = state.handle_action(action, pressed, target, &world, &mut scores);
+                    state = next;
+                    events.extend(event);
                 },
                 _ => {}
-            }
-        }
-
-        if player_alive {
-            player.tick(delta);
-            time_alive += delta;
-        }
-        
-        for a in &mut asteroids {
-            a.tick(delta);
-        }
-        for i in 0..asteroids.len() {
-            for j in 0..i {
-                if asteroids[i].shape.centre.dist(asteroids[j].shape.centre) < asteroids[i].shape.radius + asteroids[j].shape.radius {
-                    let col = asteroids[i].collides(&asteroids[j]);
-                    if let Some((col, shift, norm)) = col {
-                        //println!("{:?}, {:?}, {:?}", col, shift, norm);
-                        let (p1, p2) = asteroids.split_at_mut(j + 1);
-                        let a1 = &mut p1[j];
-                        let a2 = &mut p2[i - (j + 1)];
-
-                        a1.solve_polygon_collision(a2, col, shift, norm);
-                        asteroids[i].render(&mut canvas).unwrap();
-                        asteroids[j].render(&mut canvas).unwrap();
-                        canvas.set_draw_color(Color::RGB(0xff, 0, 0));
-
-                        fn draw_point<T: RenderTarget> (canvas: &mut Canvas<T>, p: Point, color: Color) {
-                            canvas.set_draw_color(color);
-                            canvas.draw_rect(sdl2::rect::Rect::new(p.x as i32 - 2, p.y as i32 - 2, 4, 4)).unwrap();
-                        }
-
-                        //draw_point(&mut canvas, col, Color::BLUE);
-                        //draw_point(&mut canvas, col + shift, Color::RED);
-                        canvas.set_draw_color(Color::RED);
-                        //draw_point(&mut canvas, Point::new(152.6614114901822, 197.64510669436532), Color::YELLOW);
-                        //draw_point(&mut canvas, Point::new(135.06919566423753, 83.07936587590183), Color::YELLOW);
-                        //draw_point(&mut canvas, Point::new(142.13933756665278, 129.12223237753375), Color::YELLOW);
-                        //draw_point(&mut canvas, Point::new(148.820142620565, 172.62962154544064), Color::YELLOW);
-
-                        //canvas.draw_rect(sdl2::rect::Rect::new(col.x as i32 - 2, col.y as i32 - 2, 4, 4)).unwrap();
-                        canvas.draw_line(sdl2::rect::Point::new(col.x as i32, col.y as i32), 
-                                        sdl2::rect::Point::new((col.x + norm.x * 5.0) as i32, (col.y + norm.y * 5.0) as i32)).unwrap();
-                        canvas.set_draw_color(Color::RGB(0xff, 0, 0));
-                        //canvas.draw_line(sdl2::rect::Point::new(col.x as i32, col.y as i32), 
-                        //                 sdl2::rect::Point::new((col.x + shift.x) as i32, (col.y + shift.y) as i32)).unwrap();
-
-
-                        canvas.present();
+            };
+            for input_event in events {
+                // Live input is ignored while a replay is driving the game
+                if playback.is_none() {
+                    if let Some(recording) = &mut recording {
+                        recording.record(world.tick, input_event);
                     }
-                }
-            }
-            if player_alive {
-                let player_col = asteroids[i].shape.get_collision(&player.shape);
-                if let Some((_player_col, _shift, _norm)) = player_col {
-                    player_alive = false;
+                    input.apply(input_event);
                 }
             }
         }
 
-        spawn_time -= delta;
-        if spawn_time < 0.0 {
-            spawn_time = TIME_BETWEEN_METEOROIDS;
-            meteoroid_spawner = meteoroid_spawner.rotated(2.0 * 3.1415 * rand::random::<f64>() as f64, screen_centre);
-            let vel = Point::new(screen_centre.x - meteoroid_spawner.x, screen_centre.y - meteoroid_spawner.y) / ((2.0 + rand::random::<f64>()) * 5.0);
-            asteroids.push(Asteroid::get_randomized(75.0 + 25.0 * rand::random::<f64>(), meteoroid_spawner, vel));
+        state.advance(elapsed);
+        let steps = if state.steps_world() { timestep.advance(elapsed) } else { 0 };
+        for _ in 0..steps {
+            if let Some(playback) = &mut playback {
+                playback.apply_due(world.tick, &mut input);
+            }
+            world.step(timestep.step(), &input);
+            input.clear_triggers();
+            state = state.update(&world, &scores);
+        }
+        let events = world.take_events();
+        audio.play_events(&events, &camera);
+        particles.emit_events(&events);
+        let intensity = if state == GameState::Playing { music::intensity(&world) } else { 0.0 };
+        audio.update_music(intensity, elapsed);
+        let alpha = timestep.alpha();
+        if state.steps_world() {
+            particles.update(elapsed);
         }
+        camera.follow(world.player.render_pos(alpha), world.player.vel(), elapsed);
+        background.select(&world);
 
         // Draw stuff
         canvas.set_draw_color(Color::RGB(0x00, 0x00, 0x00));
         canvas.clear();
-        if player_alive {
-            player.render(&mut canvas).unwrap();
+        background.render(&mut canvas, &camera).unwrap();
+        let frame = Frame { text: &text, camera: &camera, particles: &particles, scores: &scores, controls: &controls, alpha };
+        state.render(&mut canvas, &world, &frame).unwrap();
+        if world.debug.enabled {
+            overlay::render(&mut canvas, &text, &world, &camera).unwrap();
         }
-        for a in &asteroids {
-            a.render(&mut canvas).unwrap();
+        if let Some(playback) = &playback {
+            let status = if playback.finished() { "replay finished" } else { "replay" };
+            text.draw(&mut canvas, FIELD_WIDTH / 2.0, 20.0, status, Style::new(Color::RGB(0x80, 0x80, 0x80), 18.0).align(Align::Centre)).unwrap();
         }
 
-        canvas.string(20, 20, &(time_alive as i32).to_string(), Color::RGB(0xff, 0xff, 0xff)).unwrap();
+        if options.stress.is_some() {
+            frame_time += (elapsed - frame_time) * 0.05;
+            let stats = format!("{} asteroids   {:.0} fps", world.asteroids.len(), 1.0 / frame_time);
+            text.draw(&mut canvas, FIELD_WIDTH / 2.0, 44.0, &stats, Style::new(Color::RGB(0x80, 0x80, 0x80), 18.0).align(Align::Centre)).unwrap();
+        }
+        if volume_notice > 0.0 {
+            volume_notice -= elapsed;
+            let levels = format!("music {:.0}%   effects {:.0}%", audio.music_volume * 100.0, audio.effects_volume * 100.0);
+            text.draw(&mut canvas, FIELD_WIDTH / 2.0, FIELD_HEIGHT - 40.0, &levels, Style::new(Color::RGB(0x80, 0x80, 0x80), 18.0).align(Align::Centre)).unwrap();
+        }
 
         canvas.present();
 
-        time = Instant::now();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+        // Vsync paces the loop, this only keeps it from spinning where vsync is unavailable
+        ::std::thread::sleep(Duration::from_millis(1));
+    }
+
+    if let (Some(recording), Some(path)) = (&recording, &options.record) {
+        if let Err(e) = recording.save(path) {
+            eprintln!("{}", e);
+        }
     }
 }
@@ -0,0 +1,51 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use crate::camera::Camera;
+use crate::shapes::Point;
+use crate::world::World;
+use crate::{ARENA_WIDTH, ARENA_HEIGHT, FIELD_WIDTH, FIELD_HEIGHT};
+
+const WIDTH: f64 = 240.0;
+const MARGIN: f64 = 20.0;
+
+fn rect(top_left: Point, bottom_right: Point) -> Rect {
+    let size = bottom_right - top_left;
+    return Rect::new(top_left.x as i32, top_left.y as i32, size.x.max(1.0) as u32, size.y.max(1.0) as u32);
+}
+
+// The whole arena including the margin asteroids bounce in, scaled down into the bottom right corner.
+// The part on screen is outlined and asteroids outside it are drawn brighter.
+pub fn render<T: RenderTarget>(canvas: &mut Canvas<T>, world: &World, camera: &Camera) -> Result<(), String> {
+    let margin = world.tuning.window_margin;
+    let scale = WIDTH / (ARENA_WIDTH + 2.0 * margin);
+    let height = (ARENA_HEIGHT + 2.0 * margin) * scale;
+    let origin = Point::new(FIELD_WIDTH - MARGIN - WIDTH, FIELD_HEIGHT - MARGIN - height);
+    let to_map = |p: Point| origin + (p + Point::new(margin, margin)) * scale;
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0x00, 0x00, 0x00, 0xa0));
+    canvas.fill_rect(rect(origin, origin + Point::new(WIDTH, height)))?;
+    canvas.set_blend_mode(BlendMode::None);
+    canvas.set_draw_color(Color::RGB(0x40, 0x40, 0x40));
+    canvas.draw_rect(rect(origin, origin + Point::new(WIDTH, height)))?;
+    canvas.draw_rect(rect(to_map(Point::new(0.0, 0.0)), to_map(Point::new(ARENA_WIDTH, ARENA_HEIGHT))))?;
+
+    for a in &world.asteroids {
+        let p = to_map(a.shape().centre);
+        let r = (a.shape().radius * scale).max(1.0);
+        let color = if camera.visible(a.shape().centre, a.shape().radius) { Color::RGB(0x80, 0x80, 0x80) } else { Color::RGB(0xff, 0xff, 0xff) };
+        canvas.filled_circle(p.x as i16, p.y as i16, r as i16, color)?;
+    }
+
+    let half = camera.half_view();
+    canvas.set_draw_color(Color::RGB(0xa0, 0xa0, 0xa0));
+    canvas.draw_rect(rect(to_map(camera.centre - half), to_map(camera.centre + half)))?;
+
+    if world.player_alive {
+        let p = to_map(world.player.pos());
+        canvas.filled_circle(p.x as i16, p.y as i16, 2, Color::RGB(0xff, 0x00, 0x00))?;
+    }
+    return Ok(());
+}
@@ -0,0 +1,117 @@
+use std::f64::consts::PI;
+use sdl2::mixer::{self, Channel, Chunk};
+use crate::audio::{synth, Noise};
+use crate::world::World;
+
+// Every layer is the same four bar loop, so layers started together stay in time
+const BAR: f64 = 2.0;
+const LOOP_LENGTH: f64 = BAR * 4.0;
+const ROOTS: [f64; 4] = [110.0, 87.31, 130.81, 98.0];
+// Channels 0..LAYERS are kept out of the effects pool for the music
+pub const LAYERS: usize = 3;
+// How far intensity moves per second, danger is picked up faster than it fades
+const RISE_RATE: f64 = 1.5;
+const FALL_RATE: f64 = 0.2;
+
+// Asteroid count where the field counts as fully crowded
+const CROWDED: f64 = 20.0;
+// Distance from an asteroid's edge to the player where closeness starts to count
+const DANGER_DISTANCE: f64 = 400.0;
+// Seconds after a new meteoroid arrives that it still adds tension
+const SPAWN_SURGE: f64 = 6.0;
+
+fn root(t: f64) -> f64 {
+    return ROOTS[((t / BAR) as usize).min(ROOTS.len() - 1)];
+}
+
+// Slow chord pad that is always playing
+fn pad(t: f64) -> f64 {
+    let r = root(t);
+    let envelope = (PI * (t % BAR) / BAR).sin().sqrt();
+    let tone = (2.0 * PI * r * t).sin() + 0.6 * (2.0 * PI * r * 1.5 * t).sin() + 0.4 * (2.0 * PI * r * 2.0 * t).sin();
+    return 0.15 * envelope * tone;
+}
+
+// Eighth note bass pulse
+fn pulse(t: f64) -> f64 {
+    let r = root(t);
+    let tn = t % (BAR / 8.0);
+    let envelope = (tn * 200.0).min(1.0) * (-tn * 9.0).exp();
+    let tone = (2.0 * PI * r * tn).sin() + 0.5 * (2.0 * PI * r * 2.0 * tn).sin() + 0.25 * (2.0 * PI * r * 3.0 * tn).sin();
+    return 0.3 * envelope * tone;
+}
+
+// Sixteenth note arpeggio over off beat noise hats
+fn drive(t: f64, noise: &mut Noise) -> f64 {
+    const STEPS: [f64; 4] = [2.0, 3.0, 4.0, 3.0];
+    let step = BAR / 16.0;
+    let n = (t / step) as usize;
+    let tn = t % step;
+    let freq = root(t) * STEPS[n % STEPS.len()];
+    let note = (2.0 * PI * freq * tn).sin().signum() * (tn * 400.0).min(1.0) * (-tn * 14.0).exp();
+    let hat = if n % 4 == 2 { noise.next().unwrap() * (-tn * 60.0).exp() } else { 0.0 };
+    return 0.12 * note + 0.2 * hat;
+}
+
+// How tense the game currently is from 0 to 1, picking which music layers are heard
+pub fn intensity(world: &World) -> f64 {
+    if !world.player_alive {
+        return 0.0;
+    }
+    let count = (world.asteroids.len() as f64 / CROWDED).min(1.0);
+    let closest = world.asteroids.iter()
+        .map(|a| world.player.pos().dist(a.shape().centre) - a.shape().radius)
+        .fold(f64::MAX, f64::min);
+    let closeness = (1.0 - closest / DANGER_DISTANCE).clamp(0.0, 1.0);
+    let spawn = (1.0 - world.time_since_spawn() / SPAWN_SURGE).clamp(0.0, 1.0);
+    return (0.35 * count + 0.25 * spawn + 0.6 * closeness).min(1.0);
+}
+
+// Looping layers that fade in on top of each other as intensity rises
+pub struct Music {
+    layers: Vec<Chunk>,
+    intensity: f64,
+    playing: bool,
+}
+
+impl Music {
+    pub fn synthesize(rate: i32, channels: i32) -> Result<Music, String> {
+        let mut noise = Noise(0x9e3779b9);
+        let layers = vec![
+            synth(rate, channels, LOOP_LENGTH, pad)?,
+            synth(rate, channels, LOOP_LENGTH, pulse)?,
+            synth(rate, channels, LOOP_LENGTH, |t| drive(t, &mut noise))?,
+        ];
+        return Ok(Music { layers, intensity: 0.0, playing: false });
+    }
+
+    // Volume of a layer at the given intensity, the first layer is always on
+    fn layer_volume(layer: usize, intensity: f64) -> f64 {
+        match layer {
+            0 => 1.0,
+            1 => ((intensity - 0.2) / 0.3).clamp(0.0, 1.0),
+            _ => ((intensity - 0.55) / 0.3).clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn update(&mut self, target: f64, delta: f64, volume: f64) {
+        if !self.playing {
+            for (i, layer) in self.layers.iter().enumerate() {
+                Channel(i as i32).set_volume(0);
+                if let Err(e) = Channel(i as i32).play(layer, -1) {
+                    eprintln!("Could not play music: {}", e);
+                }
+            }
+            self.playing = true;
+        }
+        if target > self.intensity {
+            self.intensity = (self.intensity + RISE_RATE * delta).min(target);
+        } else {
+            self.intensity = (self.intensity - FALL_RATE * delta).max(target);
+        }
+        for i in 0..self.layers.len() {
+            let v = Music::layer_volume(i, self.intensity) * volume;
+            Channel(i as i32).set_volume((v * mixer::MAX_VOLUME as f64) as i32);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+pub struct Options {
+    pub tick_rate: f64,
+    pub max_catch_up: u32,
+    pub seed: Option<u64>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub bindings: Option<String>,
+    pub tuning: Option<String>,
+    pub font: Option<String>,
+    pub music_volume: f64,
+    pub effects_volume: f64,
+    pub stress: Option<usize>,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options { tick_rate: 60.0, max_catch_up: 5, seed: None, record: None, replay: None, bindings: None, tuning: None, font: None, music_volume: 0.6, effects_volume: 1.0, stress: None }
+    }
+
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--tick-rate" => {
+                    options.tick_rate = parse_value(&arg, args.next())?;
+                    if options.tick_rate.is_nan() || options.tick_rate <= 0.0 {
+                        return Err(format!("{} must be positive", arg));
+                    }
+                },
+                "--max-catch-up" => {
+                    options.max_catch_up = parse_value(&arg, args.next())?;
+                    if options.max_catch_up == 0 {
+                        return Err(format!("{} must be at least 1", arg));
+                    }
+                },
+                "--seed" => {
+                    options.seed = Some(parse_value(&arg, args.next())?);
+                },
+                "--record" => {
+                    options.record = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+                },
+                "--replay" => {
+                    options.replay = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+                },
+                "--bindings" => {
+                    options.bindings = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+                },
+                "--tuning" => {
+                    options.tuning = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+                },
+                "--font" => {
+                    options.font = Some(args.next().ok_or(format!("Missing value for {}", arg))?);
+                },
+                "--music-volume" => {
+                    options.music_volume = parse_volume(&arg, args.next())?;
+                },
+                "--effects-volume" => {
+                    options.effects_volume = parse_volume(&arg, args.next())?;
+                },
+                "--stress" => {
+                    options.stress = Some(parse_value(&arg, args.next())?);
+                },
+                _ => return Err(format!("Unknown option '{}'", arg))
+            }
+        }
+        // Replays only know about the normal start, not the stress scene
+        if options.stress.is_some() && (options.record.is_some() || options.replay.is_some()) {
+            return Err("--stress cannot be used with --record or --replay".to_string());
+        }
+        return Ok(options);
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}", name))?;
+    return value.parse::<T>().map_err(|_| format!("Invalid value '{}' for {}", value, name));
+}
+
+fn parse_volume(name: &str, value: Option<String>) -> Result<f64, String> {
+    let volume: f64 = parse_value(name, value)?;
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(format!("{} must be between 0 and 1", name));
+    }
+    return Ok(volume);
+}
@@ -0,0 +1,62 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::camera::Camera;
+use crate::debug::RAY_TIME;
+use crate::shapes::Point;
+use crate::text::{Style, Text};
+use crate::world::World;
+
+// Velocity vectors are drawn as the distance covered in this many seconds
+const VEL_SCALE: f64 = 0.5;
+// Angular velocity arcs span the angle turned in this many seconds
+const ANG_VEL_SCALE: f64 = 1.0;
+const NORMAL_LENGTH: f64 = 30.0;
+
+fn line<T: RenderTarget>(canvas: &mut Canvas<T>, camera: &Camera, a: Point, b: Point, color: Color) -> Result<(), String> {
+    let (a, b) = (camera.to_screen(a), camera.to_screen(b));
+    return canvas.aa_line(a.x as i16, a.y as i16, b.x as i16, b.y as i16, color);
+}
+
+// Bounding circle, centroid, velocity and angular velocity of a body
+fn body<T: RenderTarget>(canvas: &mut Canvas<T>, camera: &Camera, centre: Point, radius: f64, vel: Point, ang_vel: f64) -> Result<(), String> {
+    let c = camera.to_screen(centre);
+    let r = (radius * camera.zoom) as i16;
+    canvas.aa_circle(c.x as i16, c.y as i16, r, Color::RGB(0x00, 0x80, 0x00))?;
+    canvas.filled_circle(c.x as i16, c.y as i16, 3, Color::RGB(0xff, 0xff, 0x00))?;
+    line(canvas, camera, centre, centre + vel * VEL_SCALE, Color::RGB(0x00, 0xc0, 0xff))?;
+    if ang_vel != 0.0 {
+        // SDL_gfx arcs go clockwise in degrees, which matches positive rotation with y pointing down
+        let sweep = (ang_vel * ANG_VEL_SCALE).to_degrees().clamp(-359.0, 359.0) as i16;
+        let (start, end) = if sweep > 0 { (0, sweep) } else { (sweep, 0) };
+        canvas.arc(c.x as i16, c.y as i16, r / 2, start, end, Color::RGB(0xff, 0x60, 0xff))?;
+    }
+    return Ok(());
+}
+
+// Drawn over everything at the bodies' latest simulated positions, without interpolation
+pub fn render<T: RenderTarget>(canvas: &mut Canvas<T>, text: &Text, world: &World, camera: &Camera) -> Result<(), String> {
+    for a in &world.asteroids {
+        if camera.visible(a.shape().centre, a.shape().radius) {
+            body(canvas, camera, a.shape().centre, a.shape().radius, a.vel(), a.ang_vel())?;
+        }
+    }
+    if world.player_alive {
+        body(canvas, camera, world.player.shape().centre, world.player.shape().radius, world.player.vel(), 0.0)?;
+    }
+    for c in &world.debug.contacts {
+        let p = camera.to_screen(c.point);
+        canvas.filled_circle(p.x as i16, p.y as i16, 4, Color::RGB(0xff, 0x00, 0x00))?;
+        line(canvas, camera, c.point, c.point + c.shift, Color::RGB(0xff, 0xa0, 0x00))?;
+        line(canvas, camera, c.point, c.point + c.normal * NORMAL_LENGTH, Color::RGB(0xff, 0x00, 0xff))?;
+    }
+    for (start, end, time) in &world.debug.rays {
+        let alpha = (255.0 * time / RAY_TIME) as u8;
+        line(canvas, camera, *start, *end, Color::RGBA(0xff, 0xff, 0xff, alpha))?;
+    }
+
+    let info = format!("debug   asteroids {}   contacts {}   camera {:.0}, {:.0} x{:.2}",
+        world.asteroids.len(), world.debug.contacts.len(), camera.centre.x, camera.centre.y, camera.zoom);
+    text.draw(canvas, 20.0, 130.0, &info, Style::new(Color::RGB(0x00, 0xff, 0x00), 14.0))?;
+    return Ok(());
+}
@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use crate::camera::Camera;
+use crate::shapes::Point;
+use crate::world::GameEvent;
+
+// Emitting into a full pool drops the new particles
+const MAX_PARTICLES: usize = 4096;
+// Particles are only decoration, they have their own RNG so the world's stays untouched
+const PARTICLE_SEED: u64 = 0xd057_c10d;
+// Impulse from solve_polygon_collision worth one spark
+const SPARK_IMPULSE: f64 = 1.0e5;
+const MAX_SPARKS: usize = 16;
+
+#[derive(Copy, Clone)]
+struct Particle {
+    pos: Point,
+    vel: Point,
+    life: f64,
+    max_life: f64,
+    // Fraction of velocity lost per second
+    drag: f64,
+    size: f64,
+    color: Color,
+}
+
+// Fixed size pool of short lived particles. Alive particles are kept at the front,
+// a dead one is swapped with the last alive so nothing is allocated after start up.
+pub struct Particles {
+    pool: Vec<Particle>,
+    alive: usize,
+    rng: StdRng,
+}
+
+impl Particles {
+    pub fn new() -> Particles {
+        let empty = Particle {
+            pos: Point::new(0.0, 0.0), vel: Point::new(0.0, 0.0), life: 0.0, max_life: 1.0, drag: 0.0, size: 0.0, color: Color::RGB(0, 0, 0)
+        };
+        return Particles { pool: vec![empty; MAX_PARTICLES], alive: 0, rng: StdRng::seed_from_u64(PARTICLE_SEED) };
+    }
+
+    fn emit(&mut self, pos: Point, vel: Point, life: f64, drag: f64, size: f64, color: Color) {
+        if self.alive == self.pool.len() {
+            return;
+        }
+        self.pool[self.alive] = Particle { pos, vel, life, max_life: life, drag, size, color };
+        self.alive += 1;
+    }
+
+    // A velocity in a random direction within spread radians of dir, with a speed between min and max
+    fn random_vel(&mut self, dir: f64, spread: f64, min: f64, max: f64) -> Point {
+        let angle = dir + self.rng.gen_range(-spread..=spread);
+        let speed = self.rng.gen_range(min..=max);
+        return Point::new(angle.cos() * speed, angle.sin() * speed);
+    }
+
+    // Dust drifting off both sides of a laser cut
+    fn cut_dust(&mut self, start: Point, end: Point) {
+        let length = start.dist(end);
+        if length == 0.0 {
+            return;
+        }
+        let side = ((end - start) / length).rotated(PI / 2.0, Point::new(0.0, 0.0));
+        let count = ((length / 6.0) as usize).min(80);
+        for _ in 0..count {
+            let pos = start + (end - start) * self.rng.gen::<f64>();
+            let dir = if self.rng.gen::<bool>() { side } else { -side };
+            let vel = dir * self.rng.gen_range(10.0..60.0) + self.random_vel(0.0, PI, 0.0, 15.0);
+            let grey = self.rng.gen_range(0x70..0xc0);
+            let life = self.rng.gen_range(0.6..1.4);
+            self.emit(pos, vel, life, 1.5, 2.0, Color::RGB(grey, grey - 0x10, grey - 0x20));
+        }
+    }
+
+    fn sparks(&mut self, pos: Point, normal: Point, impulse: f64) {
+        let count = ((impulse / SPARK_IMPULSE) as usize).min(MAX_SPARKS);
+        let dir = normal.y.atan2(normal.x);
+        for i in 0..count {
+            // Sparks spray out to both sides of the contact
+            let side = if i % 2 == 0 { PI / 2.0 } else { -PI / 2.0 };
+            let vel = self.random_vel(dir + side, 0.6, 150.0, 400.0);
+            let life = self.rng.gen_range(0.15..0.4);
+            let green = self.rng.gen_range(0xb0..=0xff);
+            self.emit(pos, vel, life, 3.0, 2.0, Color::RGB(0xff, green, 0x60));
+        }
+    }
+
+    fn burst(&mut self, pos: Point, count: usize, min_speed: f64, max_speed: f64, life: f64, colors: &[Color]) {
+        for _ in 0..count {
+            let vel = self.random_vel(0.0, PI, min_speed, max_speed);
+            let life = life * self.rng.gen_range(0.5..1.0);
+            let color = colors[self.rng.gen_range(0..colors.len())];
+            let size = self.rng.gen_range(2.0..4.0);
+            self.emit(pos, vel, life, 1.0, size, color);
+        }
+    }
+
+    pub fn emit_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match *event {
+                GameEvent::AsteroidSplit { start, end } => self.cut_dust(start, end),
+                GameEvent::Impact { pos, normal, impulse } => self.sparks(pos, normal, impulse),
+                GameEvent::FragmentDestroyed { pos, area } => {
+                    let count = ((area / 15.0) as usize).clamp(6, 40);
+                    self.burst(pos, count, 20.0, 90.0, 1.0, &[Color::RGB(0x90, 0x90, 0x90), Color::RGB(0xc0, 0xb0, 0xa0)]);
+                },
+                GameEvent::PlayerDied { pos } => {
+                    let colors = [Color::RGB(0xff, 0xff, 0xff), Color::RGB(0xff, 0xd0, 0x40), Color::RGB(0xff, 0x70, 0x20)];
+                    self.burst(pos, 150, 50.0, 450.0, 1.8, &colors);
+                },
+                GameEvent::LaserFired { .. } => {}
+            }
+        }
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        let mut i = 0;
+        while i < self.alive {
+            let p = &mut self.pool[i];
+            p.life -= delta;
+            if p.life <= 0.0 {
+                self.alive -= 1;
+                self.pool.swap(i, self.alive);
+                continue;
+            }
+            p.pos = p.pos + p.vel * delta;
+            p.vel = p.vel * (1.0 - p.drag * delta).max(0.0);
+            i += 1;
+        }
+    }
+
+    pub fn render<T: RenderTarget>(&self, canvas: &mut Canvas<T>, camera: &Camera) -> Result<(), String> {
+        canvas.set_blend_mode(BlendMode::Blend);
+        for p in self.pool[..self.alive].iter().filter(|p| camera.visible(p.pos, p.size)) {
+            let fade = p.life / p.max_life;
+            canvas.set_draw_color(Color::RGBA(p.color.r, p.color.g, p.color.b, (fade * 255.0) as u8));
+            let size = (p.size * camera.zoom).max(1.0);
+            let pos = camera.to_screen(p.pos);
+            canvas.fill_rect(Rect::new((pos.x - size / 2.0) as i32, (pos.y - size / 2.0) as i32, size as u32, size as u32))?;
+        }
+        canvas.set_blend_mode(BlendMode::None);
+        return Ok(());
+    }
+}
@@ -1,161 +1,224 @@
-use sdl2::render::{Canvas, RenderTarget};
-use sdl2::gfx::primitives::DrawRenderer;
-use sdl2::pixels::Color;
 use crate::asteroid::Asteroid;
-use crate::shapes::{Point, Polygon};
-use crate::{WINDOW_WIDTH, WINDOW_HEIGHT};
+use crate::body::{RigidBody, Solid};
+use crate::score::Score;
+use crate::shapes::{Point, Polygon, Rectangle};
+use crate::tuning::Tuning;
+use crate::world::GameEvent;
+use crate::world::{ARENA_WIDTH, ARENA_HEIGHT};
 
-const ACCELERATION: f64 = 2000.0;
 // const MAX_VEL: f64 = 250.0;
-const FIRING_TIME: f64 = 0.25;
-const LASER_LENGTH: f64 = 1000.0;
 
 struct Laser {
     pos_start: Point,
     pos_end: Point,
-    shape: Polygon,
+    aim: Option<Point>,
+    // Angle the emitter is drawn at, kept when there is nothing to point it at
+    facing: f64,
 }
 
 impl Laser {
     fn new(pos_start : Point, pos_end : Point) -> Laser {
-        Laser { pos_start, pos_end, shape: Polygon::new(vec![Point::new(-10.0, 10.0), Point::new(-10.0, -10.0), Point::new(10.0, -10.0), Point::new(10.0, 10.0)]) }
-    }
-
-    fn render<T: RenderTarget>(&mut self, canvas: &mut Canvas<T>, firing: bool) -> Result<(), String> {
-        if firing && (self.pos_start.x - self.pos_end.x).abs() + (self.pos_start.y - self.pos_end.y).abs() > 0.0 {
-            let rot = (self.pos_end.y - self.pos_start.y).atan2(self.pos_end.x - self.pos_start.x);
-            let centre = Point::new(0.0, 0.0);
-            self.shape.points[0] = Point::new(-10.0, 10.0).rotated(rot, centre);
-            self.shape.points[1] = Point::new(-10.0, -10.0).rotated(rot, centre);
-            self.shape.points[2] = Point::new(10.0, -10.0).rotated(rot, centre);
-            self.shape.points[3] = Point::new(10.0, 10.0).rotated(rot, centre);
-        }
-        let vx = self.shape.points.iter().map(|p| (self.pos_start.x + p.x) as i16).collect::<Vec<_>>();
-        let vy = self.shape.points.iter().map(|p| (self.pos_start.y + p.y) as i16).collect::<Vec<_>>();
-        canvas.aa_polygon(&vx, &vy, Color::RGB(0xf0, 0xf0, 0xf0))?;
-        if firing {
-            canvas.aa_line(
-                self.pos_start.x as i16,
-                self.pos_start.y as i16,
-                self.pos_end.x as i16,
-                self.pos_end.y as i16,
-                Color::RGB(0xff, 0x00, 0x00)
-            )?;
-        }
-
-        return Ok(());
+        Laser { pos_start, pos_end, aim: None, facing: 0.0 }
     }
 }
 
 pub struct Player {
-    pub shape: Polygon,
-    pos: Point,
-    vel: Point,
+    // Points along its velocity, so the heading is set rather than turned by impulses
+    solid: Solid,
     acc: f64,
     mov_dir: Vec<f64>, // [up, down, left, right]
+    stick: Point,
     laser: Laser,
     firing: f64,
+    invulnerable: f64,
+}
+
+// The ship's outline pointing along the x axis with its centre of mass at pos
+fn ship_outline(pos: Point) -> Polygon {
+    let mut shape = Polygon::new(vec![Point::new(-25.0, 45.0), Point::new(25.0, 0.0), Point::new(-25.0, -45.0)]);
+    let offset = pos - shape.centre;
+    shape.shift(offset.x, offset.y);
+    return shape;
 }
 
 impl Player {
-    pub fn new(pos: Point) -> Player {
-        let mut shape = Polygon::new(vec![Point::new(-25.0, 45.0), Point::new(25.0, 0.0), Point::new(-25.0, -45.0)]);
-        shape.shift(pos.x, pos.y);
-        Player {shape, pos,
-            vel: Point{ x: 0.0, y: 0.0 },
-            acc: ACCELERATION,
+    pub fn new(pos: Point, tuning: &Tuning) -> Player {
+        let shape = ship_outline(pos);
+        // Asteroids either destroy the ship or pass through it, so it never bounces
+        let body = RigidBody::new(pos, Point::new(0.0, 0.0), shape.area(), f64::INFINITY, 0.0, 0.0);
+        Player {solid: Solid::new(shape, body),
+            acc: tuning.acceleration,
             mov_dir: vec![0.0, 0.0, 0.0, 0.0],
+            stick: Point::new(0.0, 0.0),
             laser: Laser::new(Point{x: 0.0, y: 0.0}, Point{x: 0.0, y: 0.0}),
             firing: 0.0,
+            invulnerable: 0.0,
         }
     }
 
-    pub fn render<T: RenderTarget>(&mut self, canvas: &mut Canvas<T>) -> Result<(), String> {
-        if self.shape.corners() == 0 {
-            return Ok(());
-        }
-
-        let vx = self.shape.points.iter().map(|p | p.x as i16).collect::<Vec<_>>();
-        let vy = self.shape.points.iter().map(|p | p.y as i16).collect::<Vec<_>>();
+    // A freshly respawned ship that asteroids pass through for a while
+    pub fn respawned(pos: Point, tuning: &Tuning) -> Player {
+        let mut player = Player::new(pos, tuning);
+        player.invulnerable = tuning.invulnerable_time;
+        return player;
+    }
 
-        canvas.aa_polygon(&vx, &vy, Color::RGB(0xff, 0x00, 0x00))?;
+    // Time left until the laser can fire again
+    pub fn cooldown(&self) -> f64 {
+        return self.firing;
+    }
 
-        self.laser.render(canvas, self.firing > 0.0)?;
+    pub fn invulnerable(&self) -> bool {
+        return self.invulnerable > 0.0;
+    }
 
-        return Ok(());
+    // Seconds of invulnerability left
+    pub fn invulnerable_time(&self) -> f64 {
+        return self.invulnerable;
     }
 
-    // pub fn contains_point(&self, p : Point) -> bool {
-    //     return self.shape.contains_point(p);
-    // }
+    // Remember the current transform so rendering can interpolate towards the next one
+    pub fn store_previous(&mut self) {
+        self.solid.store_previous();
+    }
 
-    pub fn tick(&mut self, delta: f64) {
-        let ddx = (self.mov_dir[1] - self.mov_dir[0]) * self.acc * delta;
-        let ddy = (self.mov_dir[3] - self.mov_dir[2]) * self.acc * delta;
-        self.vel.y += ddx;
-        self.vel.x += ddy;
+    // The outline in the world
+    pub fn shape(&self) -> &Polygon {
+        return self.solid.shape();
+    }
 
-        let rot = self.vel.y.atan2(self.vel.x);
+    // The outline between the last two steps, for drawing
+    pub fn outline(&self, alpha: f64) -> Vec<Point> {
+        return self.solid.outline(alpha);
+    }
 
-        let dx = self.vel.x * delta;
-        let dy = self.vel.y * delta;
-        self.pos = self.pos + Point::new(dx, dy);
-        if self.pos.x < 0.0 {
-            self.pos.x = 0.0;
-            if self.vel.x < 0.0 {
-                self.vel.x = 0.0;
+    pub fn tick(&mut self, delta: f64, tuning: &Tuning) {
+        // Keys and stick add up, but together they are no faster than either on its own
+        let ddx = (self.mov_dir[1] - self.mov_dir[0] + self.stick.y).clamp(-1.0, 1.0) * self.acc * delta;
+        let ddy = (self.mov_dir[3] - self.mov_dir[2] + self.stick.x).clamp(-1.0, 1.0) * self.acc * delta;
+        let mut vel = self.solid.vel() + Point::new(ddy, ddx);
+        let angle = vel.y.atan2(vel.x);
+
+        let mut pos = self.solid.pos() + vel * delta;
+        if pos.x < 0.0 {
+            pos.x = 0.0;
+            if vel.x < 0.0 {
+                vel.x = 0.0;
             }
-        } else if self.pos.x > WINDOW_WIDTH {
-            self.pos.x = WINDOW_WIDTH;
-            if self.vel.x > 0.0 {
-                self.vel.x = 0.0;
+        } else if pos.x > ARENA_WIDTH {
+            pos.x = ARENA_WIDTH;
+            if vel.x > 0.0 {
+                vel.x = 0.0;
             }
         }
-        if self.pos.y < 0.0 {
-            self.pos.y = 0.0;
-            if self.vel.y < 0.0 {
-                self.vel.y = 0.0;
+        if pos.y < 0.0 {
+            pos.y = 0.0;
+            if vel.y < 0.0 {
+                vel.y = 0.0;
             }
-        } else if self.pos.y > WINDOW_HEIGHT {
-            self.pos.y = WINDOW_HEIGHT;
-            if self.vel.y > 0.0 {
-                self.vel.y = 0.0;
+        } else if pos.y > ARENA_HEIGHT {
+            pos.y = ARENA_HEIGHT;
+            if vel.y > 0.0 {
+                vel.y = 0.0;
             }
         }
-        let mut shape = Polygon::new(vec![Point::new(-25.0, 45.0), Point::new(25.0, 0.0), Point::new(-25.0, -45.0)]);
-        shape.shift(self.pos.x, self.pos.y);
-        shape.rotate(rot);
-        self.shape = shape;
+        self.solid.set_vel(vel);
+        self.solid.set_transform(pos, angle);
 
+        self.invulnerable = (self.invulnerable - delta).max(0.0);
         if self.firing > 0.0 {
-            self.firing = (self.firing - delta).clamp(0.0, FIRING_TIME);
+            self.firing = (self.firing - delta).clamp(0.0, tuning.firing_time);
         } else {
-            self.laser.pos_start = self.pos;
+            self.laser.pos_start = self.solid.pos();
         }
+        let dir = if self.firing > 0.0 { Some(self.laser.pos_end - self.laser.pos_start) } else { self.laser.aim };
+        if let Some(dir) = dir.filter(|d| d.x.abs() + d.y.abs() > 0.0) {
+            self.laser.facing = dir.y.atan2(dir.x);
+        }
+    }
+
+    pub fn set_mov_dir(&mut self, dir: usize, val: f64) {
+        self.mov_dir[dir] = val;
     }
 
-    pub fn set_mov_dir(&mut self, dir: usize, val: bool) {
-        self.mov_dir[dir] = if val {1.0} else {0.0};
+    pub fn set_stick(&mut self, stick: Point) {
+        self.stick = stick;
     }
 
-    pub fn fire_if_ready(&mut self, target: Point, asteroids: &mut Vec<Asteroid>) {
+    // Direction the idle laser points in, follows the aiming stick
+    pub fn set_aim(&mut self, aim: Option<Point>) {
+        self.laser.aim = aim;
+    }
+
+    pub fn pos(&self) -> Point {
+        return self.solid.pos();
+    }
+
+    // Where the ship is drawn between the last two steps
+    pub fn render_pos(&self, alpha: f64) -> Point {
+        return self.solid.render_pos(alpha);
+    }
+
+    pub fn vel(&self) -> Point {
+        return self.solid.vel();
+    }
+
+    pub fn width(&self) -> f64 {
+        return self.solid.width();
+    }
+
+    // How far the ship can move in delta seconds, speeding up included
+    pub fn travel(&self, delta: f64) -> f64 {
+        return self.solid.travel(delta) + self.acc * delta * delta;
+    }
+
+    // Everywhere the outline can be within the next delta seconds
+    pub fn reach(&self, delta: f64) -> Rectangle {
+        return self.solid.reach(self.travel(delta));
+    }
+
+    // Returns whether the laser fired
+    pub fn fire_if_ready(&mut self, target: Point, asteroids: &mut Vec<Asteroid>, tuning: &Tuning, score: &mut Score, events: &mut Vec<GameEvent>) -> bool {
         if self.firing <= 0.0 {
-            self.fire(target, asteroids);
+            self.fire(target, asteroids, tuning, score, events);
+            return true;
         }
+        return false;
+    }
+
+    // Angle of the laser emitter, along the current shot or the aim
+    pub fn laser_facing(&self) -> f64 {
+        return self.laser.facing;
+    }
+
+    // Start and end of the last shot
+    pub fn laser_ray(&self) -> (Point, Point) {
+        return (self.laser.pos_start, self.laser.pos_end);
     }
 
-    fn fire(&mut self, target: Point, asteroids: &mut Vec<Asteroid>) {
-        self.laser.pos_start = self.pos;
-        let dir = (target - self.pos) / ((target - self.pos).x * (target - self.pos).x + (target - self.pos).y * (target - self.pos).y).sqrt();
-        self.laser.pos_end = target + dir * LASER_LENGTH;
-        self.firing = FIRING_TIME;
+    fn fire(&mut self, target: Point, asteroids: &mut Vec<Asteroid>, tuning: &Tuning, score: &mut Score, events: &mut Vec<GameEvent>) {
+        let pos = self.solid.pos();
+        self.laser.pos_start = pos;
+        let dir = (target - pos) / ((target - pos).x * (target - pos).x + (target - pos).y * (target - pos).y).sqrt();
+        self.laser.pos_end = target + dir * tuning.laser_length;
+        self.firing = tuning.firing_time;
+        events.push(GameEvent::LaserFired { pos: self.laser.pos_start });
         let mut new = Vec::new();
+        let mut hits = 0;
         asteroids.retain_mut(|a| {
-            if let Some(a2) = a.split(self.laser.pos_start, self.laser.pos_end) {
-                if !a2.small() {
+            if let Some((a2, cut_start, cut_end)) = a.split(self.laser.pos_start, self.laser.pos_end) {
+                score.split(a.shape().area(), a2.shape().area(), hits);
+                hits += 1;
+                events.push(GameEvent::AsteroidSplit { start: cut_start, end: cut_end });
+                if !a2.small(tuning) {
                     new.push(a2);
+                } else {
+                    score.destroyed();
+                    events.push(GameEvent::FragmentDestroyed { pos: a2.shape().centre, area: a2.shape().area() });
                 }
-                if a.small() {
+                if a.small(tuning) {
+                    score.destroyed();
+                    events.push(GameEvent::FragmentDestroyed { pos: a.shape().centre, area: a.shape().area() });
                     return false;
                 }
             }
@@ -0,0 +1,161 @@
+use std::fs;
+use crate::shapes::Point;
+use crate::tuning::Tuning;
+use crate::world::{InputEvent, InputState};
+
+const HEADER: &str = "meteoroids-replay";
+// Bumped whenever the file format or the simulation changes, older replays would silently desync
+pub const VERSION: u32 = 9;
+
+// Every input event the game reacted to, tagged with the tick it was applied before.
+// Together with the seed, tick rate and tuning this is enough to rerun a session exactly.
+pub struct Replay {
+    pub seed: u64,
+    pub tick_rate: f64,
+    pub tuning: Tuning,
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, tick_rate: f64, tuning: Tuning) -> Replay {
+        Replay { seed, tick_rate, tuning, events: Vec::new() }
+    }
+
+    pub fn record(&mut self, tick: u64, event: InputEvent) {
+        self.events.push((tick, event));
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        return fs::write(path, self.to_text()).map_err(|e| format!("Could not write replay '{}': {}", path, e));
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("{} {}\n", HEADER, VERSION));
+        out.push_str(&format!("seed {}\n", self.seed));
+        out.push_str(&format!("tick_rate {}\n", self.tick_rate));
+        for line in self.tuning.to_text().lines() {
+            out.push_str(&format!("tuning {}\n", line));
+        }
+        for (tick, event) in &self.events {
+            let line = match event {
+                InputEvent::Move(dir, amount) => format!("{} move {} {}", tick, dir, amount),
+                InputEvent::Fire(target) => format!("{} fire {} {}", tick, target.x, target.y),
+                InputEvent::Stick(p) => format!("{} stick {} {}", tick, p.x, p.y),
+                InputEvent::Aim(dir) => format!("{} aim {} {}", tick, dir.x, dir.y),
+                InputEvent::FireAim => format!("{} fire_aim", tick),
+                InputEvent::Reset => format!("{} reset", tick),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        return out;
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Could not read replay '{}': {}", path, e))?;
+        return Replay::parse(&text, path);
+    }
+
+    // path only names the file in error messages
+    pub fn parse(text: &str, path: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().enumerate();
+
+        let header = lines.next().map(|(_, l)| l.split_whitespace().collect::<Vec<_>>()).unwrap_or_default();
+        match header.as_slice() {
+            [HEADER, version] => match version.parse::<u32>() {
+                Ok(VERSION) => {},
+                Ok(v) => return Err(format!("'{}' is a version {} replay, this build only plays version {}", path, v, VERSION)),
+                Err(_) => return Err(format!("'{}' has an invalid replay version '{}'", path, version))
+            },
+            _ => return Err(format!("'{}' is not a replay file", path))
+        }
+        let mut seed = None;
+        let mut tick_rate = None;
+        let mut tuning_text = String::new();
+        let mut events = Vec::new();
+        // The tuning lines are parsed as one block, keeping them together lets errors name the line in the replay
+        let mut tuning_start = None;
+        let mut tuning_end = 0;
+        for (ix, line) in lines {
+            if let Some(entry) = line.strip_prefix("tuning ") {
+                if tuning_start.is_some() && tuning_end != ix {
+                    return Err(format!("{}:{}: tuning lines have to follow each other", path, ix + 1));
+                }
+                tuning_start.get_or_insert(ix + 1);
+                tuning_end = ix + 1;
+                tuning_text.push_str(entry);
+                tuning_text.push('\n');
+                continue;
+            }
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let err = || format!("{}:{}: invalid replay line '{}'", path, ix + 1, line);
+            match parts.as_slice() {
+                [] => {},
+                ["seed", v] => seed = Some(v.parse::<u64>().map_err(|_| err())?),
+                // The world would never step at a rate that is not a positive number
+                ["tick_rate", v] => tick_rate = Some(v.parse::<f64>().ok().filter(|r| r.is_finite() && *r > 0.0).ok_or_else(err)?),
+                [tick, kind, args @ ..] => {
+                    let tick = tick.parse::<u64>().map_err(|_| err())?;
+                    let event = match (*kind, args) {
+                        ("move", [dir, amount]) => {
+                            let dir = dir.parse::<usize>().ok().filter(|d| *d < 4).ok_or_else(err)?;
+                            InputEvent::Move(dir, amount.parse::<f64>().map_err(|_| err())?)
+                        },
+                        ("fire", [x, y]) => {
+                            let x = x.parse::<f64>().map_err(|_| err())?;
+                            let y = y.parse::<f64>().map_err(|_| err())?;
+                            InputEvent::Fire(Point::new(x, y))
+                        },
+                        ("stick", [x, y]) => {
+                            let x = x.parse::<f64>().map_err(|_| err())?;
+                            let y = y.parse::<f64>().map_err(|_| err())?;
+                            InputEvent::Stick(Point::new(x, y))
+                        },
+                        ("aim", [x, y]) => {
+                            let x = x.parse::<f64>().map_err(|_| err())?;
+                            let y = y.parse::<f64>().map_err(|_| err())?;
+                            InputEvent::Aim(Point::new(x, y))
+                        },
+                        ("fire_aim", []) => InputEvent::FireAim,
+                        ("reset", []) => InputEvent::Reset,
+                        _ => return Err(err())
+                    };
+                    events.push((tick, event));
+                },
+                _ => return Err(err())
+            }
+        }
+
+        let seed = seed.ok_or(format!("'{}' has no seed", path))?;
+        let tick_rate = tick_rate.ok_or(format!("'{}' has no tick_rate", path))?;
+        let tuning = Tuning::parse(&tuning_text, path, tuning_start.unwrap_or(1))?;
+        return Ok(Replay { seed, tick_rate, tuning, events });
+    }
+}
+
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback { replay, next: 0 }
+    }
+
+    // Applies every recorded event that belongs before the given tick
+    pub fn apply_due(&mut self, tick: u64, input: &mut InputState) {
+        while let Some((t, event)) = self.replay.events.get(self.next) {
+            if *t > tick {
+                break;
+            }
+            input.apply(*event);
+            self.next += 1;
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        return self.next >= self.replay.events.len();
+    }
+}
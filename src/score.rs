@@ -0,0 +1,90 @@
+// Points for every asteroid the laser cuts, worth more the more evenly it is split.
+const POINTS_PER_AREA: f64 = 0.01;
+// Bonus for every piece that ends up too small to keep
+const DESTROY_BONUS: f64 = 25.0;
+// Each extra asteroid cut by the same shot raises the multiplier by this much
+const COMBO_STEP: f64 = 0.5;
+// After the last multi-split the multiplier holds for a while, then falls back to 1
+const COMBO_HOLD_TIME: f64 = 2.0;
+const COMBO_DECAY_RATE: f64 = 0.5;
+
+pub struct Score {
+    pub points: u64,
+    pub combo: f64,
+    hold: f64,
+}
+
+impl Score {
+    pub fn new() -> Score {
+        Score { points: 0, combo: 1.0, hold: 0.0 }
+    }
+
+    // hit is how many asteroids this shot has already cut
+    pub fn split(&mut self, area_a: f64, area_b: f64, hit: usize) {
+        if hit > 0 {
+            self.combo += COMBO_STEP;
+            self.hold = COMBO_HOLD_TIME;
+        }
+        self.add(area_a.min(area_b) * POINTS_PER_AREA);
+    }
+
+    pub fn destroyed(&mut self) {
+        self.add(DESTROY_BONUS);
+    }
+
+    fn add(&mut self, points: f64) {
+        self.points += (points * self.combo).round() as u64;
+    }
+
+    pub fn tick(&mut self, delta: f64) {
+        if self.hold > 0.0 {
+            self.hold -= delta;
+        } else {
+            self.combo = (self.combo - COMBO_DECAY_RATE * delta).max(1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_asteroid_after_the_first_raises_the_combo() {
+        let mut score = Score::new();
+        score.split(1000.0, 3000.0, 0);
+        assert_eq!(score.combo, 1.0);
+        assert_eq!(score.points, 10);
+        score.split(1000.0, 3000.0, 1);
+        assert_eq!(score.combo, 1.0 + COMBO_STEP);
+        assert_eq!(score.points, 25);
+        score.split(1000.0, 3000.0, 2);
+        assert_eq!(score.combo, 1.0 + 2.0 * COMBO_STEP);
+        assert_eq!(score.points, 45);
+    }
+
+    #[test]
+    fn combo_holds_and_then_decays_back_to_one() {
+        let mut score = Score::new();
+        score.split(1000.0, 1000.0, 1);
+        let combo = score.combo;
+        score.tick(COMBO_HOLD_TIME * 0.5);
+        assert_eq!(score.combo, combo);
+        score.tick(COMBO_HOLD_TIME * 0.5);
+        assert_eq!(score.combo, combo);
+        score.tick(0.2);
+        assert!(score.combo < combo && score.combo > 1.0);
+        score.tick(100.0);
+        assert_eq!(score.combo, 1.0);
+    }
+
+    #[test]
+    fn destroying_a_fragment_gives_the_bonus_times_the_combo() {
+        let mut score = Score::new();
+        score.destroyed();
+        assert_eq!(score.points, DESTROY_BONUS as u64);
+        score.split(0.0, 0.0, 1);
+        score.destroyed();
+        assert_eq!(score.points, DESTROY_BONUS as u64 + (DESTROY_BONUS * (1.0 + COMBO_STEP)).round() as u64);
+    }
+}
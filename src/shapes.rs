@@ -1,7 +1,7 @@
 use core::f64;
 use std::ops::{Add, Div, Mul, Sub, Neg};
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct Rectangle {
     pub x: f64,
     pub y: f64,
@@ -13,9 +13,19 @@ impl Rectangle {
     pub fn new(x: f64, y: f64, w: f64, h: f64) -> Rectangle {
         Rectangle {x, y, w, h}
     }
+
+    // The square reaching half_size from centre in every direction
+    pub fn around(centre: Point, half_size: f64) -> Rectangle {
+        return Rectangle::new(centre.x - half_size, centre.y - half_size, 2.0 * half_size, 2.0 * half_size);
+    }
+
+    pub fn overlaps(&self, other: &Rectangle) -> bool {
+        return self.x <= other.x + other.w && other.x <= self.x + self.w
+            && self.y <= other.y + other.h && other.y <= self.y + self.h;
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Point {
     pub x: f64,
     pub y: f64
@@ -114,25 +124,50 @@ pub fn line_intersects(pa1: Point, pa2: Point, pb1: Point, pb2: Point) -> Option
     }
     let t = ((pa1.x - pb1.x) * (pb1.y - pb2.y) - (pa1.y - pb1.y) * (pb1.x - pb2.x)) / denom;
     let u = -((pa1.x - pa2.x) * (pa1.y - pb1.y) - (pa1.y - pa2.y) * (pa1.x - pb1.x)) / denom;
-    if 0.0 <= t && t <= 1.0 && 0.0 <= u {
+    if (0.0..=1.0).contains(&t) && 0.0 <= u {
         return Some(pa1 + t * (pa2 - pa1));
     }
     return None;
 }
 
-pub fn line_segment_intersect(pa1: Point, pa2: Point, pb1: Point, pb2: Point) -> Option<Point> {
-    let denom = (pa1.x - pa2.x) * (pb1.y - pb2.y) - (pa1.y - pa2.y) * (pb1.x - pb2.x);
-    if denom == 0.0 {
-        return None;
+const MIN_EDGE: f64 = 1e-6;
+// Area below this fraction of the squared extent counts as none
+const MIN_RELATIVE_AREA: f64 = 1e-9;
+
+// Two overlapping polygons, seen from the first one
+pub struct Manifold {
+    // Unit normal pointing from the first polygon towards the second
+    pub normal: Point,
+    // How far the polygons overlap along the normal
+    pub depth: f64,
+    // One or two points where they touch
+    pub contacts: Vec<Point>,
+}
+
+impl Manifold {
+    // How to move the first polygon so they no longer overlap
+    pub fn shift(&self) -> Point {
+        return self.normal * -self.depth;
     }
-    let t = ((pa1.x - pb1.x) * (pb1.y - pb2.y) - (pa1.y - pb1.y) * (pb1.x - pb2.x)) / denom;
-    let u = -((pa1.x - pa2.x) * (pa1.y - pb1.y) - (pa1.y - pa2.y) * (pa1.x - pb1.x)) / denom;
 
-    if 0.0 <= t && t <= 1.0 && 0.0 <= u && u <= 1.0 {
-        return Some(pa1 + t * (pa2 - pa1));
+    // A single point for the contact, the middle of the two when there are two
+    pub fn contact(&self) -> Point {
+        return self.contacts.iter().fold(Point::new(0.0, 0.0), |sum, p| sum + *p) / self.contacts.len() as f64;
     }
+}
 
-    return None;
+// Keeps the part of the segment where dir.dot(p) <= limit
+fn clip(points: [Point; 2], dir: Point, limit: f64) -> Option<[Point; 2]> {
+    let d0 = dir.dot(points[0]) - limit;
+    let d1 = dir.dot(points[1]) - limit;
+    if d0 > 0.0 && d1 > 0.0 {
+        return None;
+    }
+    if d0 <= 0.0 && d1 <= 0.0 {
+        return Some(points);
+    }
+    let cut = points[0] + (points[1] - points[0]) * (d0 / (d0 - d1));
+    return Some(if d0 > 0.0 { [cut, points[1]] } else { [points[0], cut] });
 }
 
 pub struct Polygon {
@@ -167,7 +202,22 @@ impl Polygon {
         return poly;
     }
 
+    // The area centroid, or the average of the points for a polygon without area.
+    // Sums are taken relative to the first point so shapes far from the origin keep their precision.
     pub fn calc_centre(&mut self) {
+        if self.has_area() {
+            let origin = self.points[0];
+            let mut centre = Point::new(0.0, 0.0);
+            let mut doubled_area = 0.0;
+            for (p1, p2) in self.lines() {
+                let (a, b) = (p1 - origin, p2 - origin);
+                let cross = a.x * b.y - b.x * a.y;
+                doubled_area += cross;
+                centre = centre + (a + b) * cross;
+            }
+            self.centre = origin + centre / (3.0 * doubled_area);
+            return;
+        }
         let mut centre = Point::new(0.0, 0.0);
         for p in self.points.iter() {
             centre = centre + *p;
@@ -186,11 +236,45 @@ impl Polygon {
         self.radius = radius.sqrt();
     }
 
+    // Twice the signed area, relative to the first point
+    fn doubled_area(&self) -> f64 {
+        let origin = self.points[0];
+        let mut sum = 0.0;
+        for (p1, p2) in self.lines() {
+            let (a, b) = (p1 - origin, p2 - origin);
+            sum += a.x * b.y - b.x * a.y;
+        }
+        return sum;
+    }
+
+    // False for slivers whose area is lost in rounding compared to their size
+    fn has_area(&self) -> bool {
+        if self.points.len() < 3 {
+            return false;
+        }
+        let bounds = self.bounds();
+        let extent = bounds.w.max(bounds.h);
+        return self.doubled_area().abs() > MIN_RELATIVE_AREA * extent * extent;
+    }
+
+    // The smallest distance between two parallel lines that enclose the shape, which has to be convex
+    pub fn width(&self) -> f64 {
+        let mut width = f64::MAX;
+        for (p1, p2) in self.edges() {
+            let d = (p2 - p1) / p1.dist(p2);
+            let extent = self.points.iter()
+                .map(|p| ((p.x - p1.x) * d.y - (p.y - p1.y) * d.x).abs())
+                .fold(0.0, f64::max);
+            width = width.min(extent);
+        }
+        return if width == f64::MAX { 0.0 } else { width };
+    }
+
     pub fn corners(&self) -> usize {
         return self.points.len();
     }
 
-    pub fn lines(&self) -> Lines {
+    pub fn lines(&self) -> Lines<'_> {
         assert!(self.points.len() >= 3);
         return Lines {
             p: *self.points.last().unwrap(),
@@ -199,7 +283,7 @@ impl Polygon {
     }
 
     pub fn bounds(&self) -> Rectangle {
-        assert!(self.points.len() > 0);
+        assert!(!self.points.is_empty());
 
         let mut it = self.points.iter();
 
@@ -222,76 +306,79 @@ impl Polygon {
         return Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y);
     }
 
-    // Point and normal
-    fn closest_point(&self, p: Point) -> (Point, Point) {
-        let mut closest = Point::new(0.0, 0.0);
-        let mut normal = Point::new(0.0, 0.0);
-        let mut dist = f64::MAX;
-
-        for (p1, p2) in self.lines() {
-            let a = p - p1;
-            let b = p2 - p1;
-            let proj = (a.dot(b) / b.dot(b)) * b;
-            let close = p1 + proj;
-            let d = p.dist_squared(close);
-            if d < dist {
-                dist = d;
-                let norm = Point::new(b.y, -b.x);
-                let p = norm + close;
-                let d = - norm.dot(close);
-                if (p.dot(norm) + d).signum() != (self.centre.dot(norm) + d).signum() {
-                    normal = norm;
-                } else {
-                    normal = Point::new(-norm.x, -norm.y);
-                }
-
-                closest = close;
+    // Unit normal of the edge p1 -> p2 pointing away from the centre, for either winding
+    fn outward_normal(&self, p1: Point, p2: Point) -> Point {
+        let d = p2 - p1;
+        let n = Point::new(d.y, -d.x) / d.len();
+        if (p1 - self.centre).dot(n) < 0.0 {
+            return -n;
+        }
+        return n;
+    }
+
+    // The edge of self that other is furthest outside of, as (separation, edge index).
+    // A positive separation means that edge is a separating axis.
+    fn max_separation(&self, other: &Polygon) -> (f64, usize) {
+        let mut best = (f64::MIN, 0);
+        for (i, (p1, p2)) in self.edges().enumerate() {
+            let n = self.outward_normal(p1, p2);
+            let separation = other.points.iter().map(|q| (*q - p1).dot(n)).fold(f64::MAX, f64::min);
+            if separation > best.0 {
+                best = (separation, i);
             }
         }
+        return best;
+    }
 
-        return (closest, normal);
-    }
-
-    // point and normal
-    // p2 should be iside, p1 outside
-    fn get_intersect(&self, p1: Point, p2: Point) -> Option<(Point, Point)> {
-        for (p3, p4) in self.lines() {
-            if let Some(p) = line_segment_intersect(p1, p2, p3, p4) {
-                //println!("{:?}, {:?}, {:?}, {:?}", p1, p2, p3, p4);
-                let delta = p4 - p3;
-                let rotated = Point::new(-delta.y, delta.x);
-                let out = p1 - p;
-                
-                let normal = (rotated.dot(out) / rotated.dot(rotated)) * rotated;
-                return Some((p, normal / normal.len()));
-            } 
-        }
-        return None;
+    // Edges in the same order as the points, unlike lines() which starts with the closing edge.
+    // Edges shorter than MIN_EDGE, which a cut right through a corner can leave, have no normal and are skipped.
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.points.len();
+        return (0..n).map(move |i| (self.points[i], self.points[(i + 1) % n]))
+            .filter(|(p1, p2)| p1.dist_squared(*p2) > MIN_EDGE * MIN_EDGE);
     }
 
-    fn check_collision(&self, other: &Polygon) -> Option<(Point, Point, Point)> {
-        for p in &other.points {
-            if self.contains_point(*p) {
-                let centre = other.centre;
-                if let Some((col, normal)) = self.get_intersect(*p, centre) {
-                    return Some((col, col -*p, normal));
-                } else {
-                    return None;
-                }
-            }
+    // Separating axis test between two convex polygons. The edge with the shallowest penetration gives the normal,
+    // and the contacts are the edge of the other polygon facing it, clipped to its ends.
+    pub fn get_collision(&self, other: &Polygon) -> Option<Manifold> {
+        if self.edges().count() < 3 || other.edges().count() < 3 {
+            return None;
+        }
+        let (separation_a, edge_a) = self.max_separation(other);
+        if separation_a > 0.0 {
+            return None;
+        }
+        let (separation_b, edge_b) = other.max_separation(self);
+        if separation_b > 0.0 {
+            return None;
         }
-        return None;
-    } 
 
-    // Returns tuple: (Point of collision, How to move self to not intersect, normal)
-    pub fn get_collision(&self, other: &Polygon) -> Option<(Point, Point, Point)> {
-        if let Some(res) = self.check_collision(other) {
-            return Some(res);
+        // A small bias keeps the same reference edge from step to step when both are about as deep
+        let (reference, incident, edge, separation, flip) = if separation_b > separation_a + 1e-3 {
+            (other, self, edge_b, separation_b, true)
+        } else {
+            (self, other, edge_a, separation_a, false)
+        };
+        let (v1, v2) = reference.edges().nth(edge).unwrap();
+        let n = reference.outward_normal(v1, v2);
+
+        // The incident edge is the one facing most directly against the reference normal
+        let (i1, i2) = incident.edges()
+            .min_by(|a, b| incident.outward_normal(a.0, a.1).dot(n).total_cmp(&incident.outward_normal(b.0, b.1).dot(n)))
+            .unwrap();
+
+        let t = (v2 - v1) / (v2 - v1).len();
+        let mut contacts = Vec::with_capacity(2);
+        if let Some(clipped) = clip([i1, i2], -t, -t.dot(v1)).and_then(|c| clip(c, t, t.dot(v2))) {
+            contacts.extend(clipped.iter().filter(|p| (**p - v1).dot(n) <= 0.0));
         }
-        if let Some((col, offset, normal)) = other.check_collision(self) {
-            return Some((col, -offset, normal));
+        if contacts.is_empty() {
+            let deepest = incident.points.iter().min_by(|a, b| (**a - v1).dot(n).total_cmp(&(**b - v1).dot(n))).unwrap();
+            contacts.push(*deepest);
         }
-        return None;
+
+        let normal = if flip { -n } else { n };
+        return Some(Manifold { normal, depth: -separation, contacts });
     }
 
     pub fn rotate(&mut self, rad: f64) {
@@ -301,6 +388,17 @@ impl Polygon {
         self.calc_centre();
     }
 
+    // Makes this the outline local, given around the origin, turned by angle and moved to pos.
+    // Both must have the same corners, the radius stays that of local.
+    pub fn place(&mut self, local: &Polygon, pos: Point, angle: f64) {
+        let origin = Point::new(0.0, 0.0);
+        for (p, l) in self.points.iter_mut().zip(&local.points) {
+            *p = l.rotated(angle, origin) + pos;
+        }
+        self.centre = pos;
+        self.radius = local.radius;
+    }
+
     // pub fn centre(&self) -> Point {
     //     assert!(self.points.len() > 0);
 
@@ -317,27 +415,164 @@ impl Polygon {
         self.calc_centre();
     }
 
-    pub fn area(&self) -> f64 {
+    // Polar moment of inertia about the centre for a density of 1, so the mass is the area.
+    // Taken relative to the centre, which keeps it exact for shapes far from the origin.
+    pub fn inertia(&self) -> f64 {
         let mut sum = 0.0;
         for (p1, p2) in self.lines() {
-            sum += p1.x * p2.y - p1.y * p2.x;
+            let (a, b) = (p1 - self.centre, p2 - self.centre);
+            let cross = a.x * b.y - b.x * a.y;
+            sum += cross * (a.dot(a) + a.dot(b) + b.dot(b));
         }
-        return 0.5 * sum.abs();
+        return (sum / 12.0).abs();
     }
 
-    pub fn contains_point(&self, p : Point) -> bool {
-        if self.points.len() <= 2 {
-            return false;
-        }
+    pub fn area(&self) -> f64 {
+        return 0.5 * self.doubled_area().abs();
+    }
+}
 
-        let mut c = false;
-        for (p2, p1) in self.lines() {
-            if ((p1.y <= p.y && p.y < p2.y) || (p2.y <= p.y && p.y < p1.y)) &&
-                (p.x < (p2.x - p1.x) * (p.y - p1.y) / (p2.y - p1.y) + p1.x) {
-                    c = !c;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, w: f64, h: f64) -> Polygon {
+        return Polygon::new(vec![Point::new(x, y), Point::new(x + w, y), Point::new(x + w, y + h), Point::new(x, y + h)]);
+    }
 
-        return c;
+    fn close(a: f64, b: f64) -> bool {
+        return (a - b).abs() < 1e-9;
+    }
+
+    fn same_points(mut a: Vec<Point>, mut b: Vec<Point>) -> bool {
+        a.sort_by(|p, q| p.y.total_cmp(&q.y));
+        b.sort_by(|p, q| p.y.total_cmp(&q.y));
+        return a.len() == b.len() && a.iter().zip(&b).all(|(p, q)| close(p.x, q.x) && close(p.y, q.y));
+    }
+
+    #[test]
+    fn separated_shapes_do_not_collide() {
+        assert!(rect(0.0, 0.0, 100.0, 100.0).get_collision(&rect(101.0, 0.0, 50.0, 50.0)).is_none());
+        assert!(rect(0.0, 0.0, 100.0, 100.0).get_collision(&rect(150.0, 150.0, 10.0, 10.0)).is_none());
+        // Near on both axes but apart along the diagonal
+        let triangle = Polygon::new(vec![Point::new(101.0, 101.0), Point::new(150.0, 101.0), Point::new(101.0, 60.0 + 101.0)]);
+        let diamond = Polygon::new(vec![Point::new(50.0, 0.0), Point::new(100.0, 50.0), Point::new(50.0, 100.0), Point::new(0.0, 50.0)]);
+        assert!(diamond.get_collision(&triangle).is_none());
+    }
+
+    #[test]
+    fn crossing_edges_without_contained_corners_collide() {
+        // A plus sign, no corner of either rectangle is inside the other
+        let wide = rect(0.0, 40.0, 100.0, 20.0);
+        let tall = rect(40.0, 0.0, 20.0, 100.0);
+        for corner in &tall.points {
+            assert!(!(corner.x > 0.0 && corner.x < 100.0 && corner.y > 40.0 && corner.y < 60.0));
+        }
+        let m = wide.get_collision(&tall).unwrap();
+        assert!(close(m.normal.len(), 1.0));
+        assert!(m.depth > 0.0);
+        assert!(!m.contacts.is_empty());
+    }
+
+    #[test]
+    fn corner_into_face_gives_one_contact() {
+        let square = rect(0.0, 0.0, 100.0, 100.0);
+        let triangle = Polygon::new(vec![Point::new(95.0, 50.0), Point::new(150.0, 20.0), Point::new(150.0, 80.0)]);
+        let m = square.get_collision(&triangle).unwrap();
+        assert!(close(m.normal.x, 1.0) && close(m.normal.y, 0.0));
+        assert!(close(m.depth, 5.0));
+        assert!(same_points(m.contacts, vec![Point::new(95.0, 50.0)]));
+    }
+
+    #[test]
+    fn normal_points_from_self_to_other_when_flipped() {
+        // The square's face is the reference edge both ways round, so the second call flips the normal
+        let square = rect(0.0, 0.0, 100.0, 100.0);
+        let triangle = Polygon::new(vec![Point::new(95.0, 50.0), Point::new(150.0, 20.0), Point::new(150.0, 80.0)]);
+        let m = triangle.get_collision(&square).unwrap();
+        assert!(close(m.normal.x, -1.0) && close(m.normal.y, 0.0));
+        assert!(m.normal.dot(square.centre - triangle.centre) > 0.0);
+        assert!(close(m.depth, 5.0));
+        // Moving the triangle by the shift separates them
+        let mut moved = Polygon::new(triangle.points.iter().map(|p| *p + m.shift() * 1.001).collect());
+        moved.calc_radius();
+        assert!(moved.get_collision(&square).is_none());
+    }
+
+    #[test]
+    fn face_against_face_gives_two_contacts() {
+        let a = rect(0.0, 0.0, 100.0, 100.0);
+        let b = rect(90.0, 20.0, 100.0, 60.0);
+        let m = a.get_collision(&b).unwrap();
+        assert!(close(m.normal.x, 1.0) && close(m.normal.y, 0.0));
+        assert!(close(m.depth, 10.0));
+        assert!(same_points(m.contacts.clone(), vec![Point::new(90.0, 20.0), Point::new(90.0, 80.0)]));
+        assert!(close(m.contact().x, 90.0) && close(m.contact().y, 50.0));
+    }
+
+    #[test]
+    fn contained_shape_collides() {
+        let outer = rect(0.0, 0.0, 100.0, 100.0);
+        let inner = rect(40.0, 30.0, 20.0, 20.0);
+        let m = outer.get_collision(&inner).unwrap();
+        assert!(close(m.normal.len(), 1.0));
+        // Deep enough to push the outer one all the way off
+        assert!(m.depth >= 50.0);
+        assert!(!m.contacts.is_empty());
+        assert!(inner.get_collision(&outer).is_some());
+    }
+
+    #[test]
+    fn zero_length_edges_are_skipped() {
+        // A cut through a corner leaves the corner twice
+        let cut = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0), Point::new(100.0, 0.0), Point::new(100.0, 100.0), Point::new(0.0, 100.0)]);
+        let other = rect(90.0, 20.0, 100.0, 60.0);
+        for m in [cut.get_collision(&other).unwrap(), other.get_collision(&cut).unwrap()] {
+            assert!(m.normal.x.is_finite() && m.normal.y.is_finite() && m.depth.is_finite());
+            assert!(m.contacts.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+        }
+        let sliver = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(0.0, 0.0), Point::new(50.0, 50.0)]);
+        assert!(sliver.get_collision(&other).is_none());
+    }
+
+    #[test]
+    fn square_centre_area_and_inertia() {
+        let a = 30.0;
+        let square = rect(10.0, -20.0, a, a);
+        assert!(close(square.centre.x, 25.0) && close(square.centre.y, -5.0));
+        assert!(close(square.area(), a * a));
+        assert!(close(square.inertia(), a.powi(4) / 6.0));
+        // Winding does not matter
+        let reversed = Polygon::new(square.points.iter().rev().copied().collect());
+        assert!(close(reversed.inertia(), square.inertia()) && close(reversed.centre.x, 25.0));
+    }
+
+    #[test]
+    fn triangle_centre_is_the_mean_of_its_corners() {
+        let triangle = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(90.0, 0.0), Point::new(0.0, 30.0)]);
+        assert!(close(triangle.centre.x, 30.0) && close(triangle.centre.y, 10.0));
+        assert!(close(triangle.area(), 1350.0));
+    }
+
+    #[test]
+    fn shapes_far_from_the_origin_keep_their_centre() {
+        let offset = Point::new(1e7, -3e7);
+        let near = rect(0.0, 0.0, 2.0, 1.0);
+        let far = Polygon::new(near.points.iter().map(|p| *p + offset).collect());
+        assert!((far.centre.x - offset.x - 1.0).abs() < 1e-6 && (far.centre.y - offset.y - 0.5).abs() < 1e-6);
+        assert!((far.inertia() - near.inertia()).abs() < 1e-6);
+
+        // A thin sliver still has area and its centroid lies inside it
+        let sliver = Polygon::new(vec![offset, offset + Point::new(100.0, 0.0), offset + Point::new(100.0, 0.01)]);
+        assert!((sliver.centre.x - offset.x - 200.0 / 3.0).abs() < 1e-6);
+        assert!((sliver.centre.y - offset.y - 0.01 / 3.0).abs() < 1e-6);
+        assert!((sliver.area() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn flat_shapes_fall_back_to_the_mean_point() {
+        let line = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 10.0), Point::new(20.0, 20.0)]);
+        assert!(close(line.centre.x, 10.0) && close(line.centre.y, 10.0));
+        assert!(line.centre.x.is_finite() && line.inertia().is_finite());
     }
 }
@@ -0,0 +1,202 @@
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::bindings::Action;
+use crate::camera::Camera;
+use crate::draw;
+use crate::highscore::{self, Entry, HighScores};
+use crate::hud;
+use crate::indicators;
+use crate::minimap;
+use crate::particles::Particles;
+use crate::shapes::Point;
+use crate::text::{Align, Style, Text};
+use crate::world::{InputEvent, World};
+use crate::{FIELD_WIDTH, FIELD_HEIGHT};
+
+// Everything besides the world and the state that a frame is drawn from
+pub struct Frame<'a, 't> {
+    pub text: &'a Text<'t>,
+    pub camera: &'a Camera,
+    pub particles: &'a Particles,
+    pub scores: &'a HighScores,
+    // Describes the controls on the title screen
+    pub controls: &'a str,
+    // How far rendering is between the last two steps
+    pub alpha: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    NameEntry { name: String },
+    // shown counts the seconds since the screen came up
+    GameOver { rank: Option<usize>, shown: f64 },
+}
+
+// Seconds the game over screen ignores fire, so a player still shooting when the ship died does not skip it
+const RESTART_DELAY: f64 = 1.0;
+
+fn render_scores<T: RenderTarget>(canvas: &mut Canvas<T>, text: &Text, y: f64, scores: &HighScores, highlight: Option<usize>) -> Result<(), String> {
+    if scores.entries.is_empty() {
+        return Ok(());
+    }
+    let cx = FIELD_WIDTH / 2.0;
+    let header = Style::new(Color::RGB(0x80, 0x80, 0x80), 16.0);
+    text.draw(canvas, cx - 300.0, y, "NAME", header)?;
+    text.draw(canvas, cx, y, "TIME", header.align(Align::Right))?;
+    text.draw(canvas, cx + 120.0, y, "SCORE", header.align(Align::Right))?;
+    text.draw(canvas, cx + 150.0, y, "DATE", header)?;
+    text.draw(canvas, cx + 270.0, y, "SEED", header)?;
+    for (i, e) in scores.entries.iter().enumerate() {
+        let color = if Some(i) == highlight { Color::RGB(0xff, 0xff, 0x00) } else { Color::RGB(0xff, 0xff, 0xff) };
+        let row = Style::new(color, 20.0).shadow();
+        let y = y + 26.0 * (i as f64 + 1.0);
+        text.draw(canvas, cx - 320.0, y, &format!("{}.", i + 1), row.align(Align::Right))?;
+        text.draw(canvas, cx - 300.0, y, &e.name, row)?;
+        text.draw(canvas, cx, y, &format!("{:.1}", e.time), row.align(Align::Right))?;
+        text.draw(canvas, cx + 120.0, y, &e.score.to_string(), row.align(Align::Right))?;
+        text.draw(canvas, cx + 150.0, y, &highscore::format_date(e.date), row)?;
+        text.draw(canvas, cx + 270.0, y, &e.seed.to_string(), Style::new(Color::RGB(0x80, 0x80, 0x80), 14.0))?;
+    }
+    return Ok(());
+}
+
+impl GameState {
+    // Handles a bound action, returning the state to switch to and any input it sends to the world.
+    // Movement always reaches the world so keys released in a menu do not stay held.
+    pub fn handle_action(self, action: Action, pressed: bool, target: Option<Point>, world: &World, scores: &mut HighScores) -> (GameState, Option<InputEvent>) {
+        if action.is_movement() {
+            return (self, action.input_event(pressed, target));
+        }
+        if !pressed {
+            return (self, None);
+        }
+        match (self, action) {
+            (GameState::Title, Action::Fire | Action::Pause | Action::Reset) => {
+                // The first run starts on the world as created, later ones need a fresh one
+                let reset = if world.player_alive { None } else { Some(InputEvent::Reset) };
+                return (GameState::Playing, reset);
+            },
+            (GameState::Playing, Action::Pause) => return (GameState::Paused, None),
+            (GameState::Playing, _) => return (GameState::Playing, action.input_event(pressed, target)),
+            (GameState::Paused, Action::Pause | Action::Fire) => return (GameState::Playing, None),
+            (GameState::Paused, Action::Reset) => return (GameState::Playing, Some(InputEvent::Reset)),
+            // Controllers cannot type, so pause keeps the suggested name. Fire does not, the player may still be shooting.
+            (state @ GameState::NameEntry { .. }, Action::Pause) => return (state.confirm_name(world, scores), None),
+            (GameState::GameOver { shown, .. }, Action::Fire | Action::Reset) if shown >= RESTART_DELAY => return (GameState::Playing, Some(InputEvent::Reset)),
+            (GameState::GameOver { .. }, Action::Pause) => return (GameState::Title, None),
+            (state, _) => return (state, None)
+        }
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        if let GameState::NameEntry { name } = self {
+            for c in text.chars().filter(|c| !c.is_control()) {
+                if name.chars().count() < highscore::MAX_NAME_LEN {
+                    name.push(c);
+                }
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let GameState::NameEntry { name } = self {
+            name.pop();
+        }
+    }
+
+    pub fn confirm_name(self, world: &World, scores: &mut HighScores) -> GameState {
+        if let GameState::NameEntry { name } = self {
+            let name = if name.trim().is_empty() { "Anonymous" } else { name.as_str() };
+            let rank = scores.insert(Entry::new(name, world.time_alive, world.score.points, world.seed));
+            if let Err(e) = scores.save() {
+                eprintln!("{}", e);
+            }
+            return GameState::GameOver { rank, shown: 0.0 };
+        }
+        return self;
+    }
+
+    // Real time passing, called once per frame
+    pub fn advance(&mut self, elapsed: f64) {
+        if let GameState::GameOver { shown, .. } = self {
+            *shown += elapsed;
+        }
+    }
+
+    pub fn focus_lost(self) -> GameState {
+        if self == GameState::Playing {
+            return GameState::Paused;
+        }
+        return self;
+    }
+
+    // Transitions caused by the simulation itself, checked after stepping
+    pub fn update(self, world: &World, scores: &HighScores) -> GameState {
+        match self {
+            GameState::Playing if !world.player_alive => {
+                // A table that is not saved, like the one replays get, never asks for a name
+                if scores.saved() && scores.qualifies(world.time_alive, world.score.points) {
+                    return GameState::NameEntry { name: scores.last_name() };
+                }
+                return GameState::GameOver { rank: None, shown: 0.0 };
+            },
+            GameState::NameEntry { .. } | GameState::GameOver { .. } if world.player_alive => GameState::Playing,
+            state => state
+        }
+    }
+
+    // The asteroids keep drifting behind the end of run screens
+    pub fn steps_world(&self) -> bool {
+        return matches!(self, GameState::Playing | GameState::NameEntry { .. } | GameState::GameOver { .. });
+    }
+
+    pub fn render<T: RenderTarget>(&self, canvas: &mut Canvas<T>, world: &World, frame: &Frame) -> Result<(), String> {
+        let Frame { text, camera, particles, scores, controls, alpha } = *frame;
+        if world.player_alive && *self != GameState::Title {
+            draw::player(canvas, camera, &world.player, alpha)?;
+        }
+        for a in &world.asteroids {
+            draw::asteroid(canvas, camera, a, alpha)?;
+        }
+        particles.render(canvas, camera)?;
+
+        let cx = FIELD_WIDTH / 2.0;
+        let middle = FIELD_HEIGHT / 2.0;
+        let title = Style::new(Color::RGB(0xff, 0xff, 0xff), 64.0).align(Align::Centre).shadow();
+        let line = Style::new(Color::RGB(0xff, 0xff, 0xff), 24.0).align(Align::Centre).shadow();
+        let hint = Style::new(Color::RGB(0x80, 0x80, 0x80), 18.0).align(Align::Centre);
+        match self {
+            GameState::Title => {
+                text.draw(canvas, cx, middle - 260.0, "METEOROIDS", title)?;
+                text.draw(canvas, cx, middle - 170.0, controls, hint)?;
+                text.draw(canvas, cx, middle - 145.0, "Fire to start", hint)?;
+                render_scores(canvas, text, middle - 90.0, scores, None)?;
+            },
+            GameState::Playing | GameState::Paused => {
+                indicators::render(canvas, world, camera)?;
+                hud::render(canvas, text, world)?;
+                minimap::render(canvas, world, camera)?;
+                if *self == GameState::Paused {
+                    text.draw(canvas, cx, middle - 32.0, "PAUSED", title)?;
+                }
+            },
+            GameState::NameEntry { name } => {
+                text.draw(canvas, cx, middle - 260.0, "NEW HIGH SCORE", title)?;
+                text.draw(canvas, cx, middle - 180.0, &format!("You survived {:.1} seconds and scored {}", world.time_alive, world.score.points), line)?;
+                text.draw(canvas, cx, middle - 145.0, &format!("Name: {}_", name), line)?;
+                text.draw(canvas, cx, middle - 115.0, "Enter to save", hint)?;
+                render_scores(canvas, text, middle - 70.0, scores, None)?;
+            },
+            GameState::GameOver { rank, .. } => {
+                text.draw(canvas, cx, middle - 260.0, "GAME OVER", title)?;
+                text.draw(canvas, cx, middle - 180.0, &format!("You survived {:.1} seconds and scored {}", world.time_alive, world.score.points), line)?;
+                text.draw(canvas, cx, middle - 145.0, "Fire to play again, pause for the title screen", hint)?;
+                render_scores(canvas, text, middle - 90.0, scores, *rank)?;
+            }
+        }
+        return Ok(());
+    }
+}
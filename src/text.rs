@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+
+// Glyphs are rendered once at this size and scaled when drawn
+const FONT_SIZE: u16 = 48;
+// Size of the gfx bitmap font used when no TTF font could be loaded
+const BITMAP_SIZE: f64 = 8.0;
+
+const FONT_PATHS: [&str; 6] = [
+    "assets/font.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Centre,
+    Right,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Style {
+    pub color: Color,
+    pub size: f64,
+    pub align: Align,
+    pub shadow: bool,
+}
+
+impl Style {
+    pub fn new(color: Color, size: f64) -> Style {
+        Style { color, size, align: Align::Left, shadow: false }
+    }
+
+    pub fn align(mut self, align: Align) -> Style {
+        self.align = align;
+        return self;
+    }
+
+    pub fn shadow(mut self) -> Style {
+        self.shadow = true;
+        return self;
+    }
+}
+
+struct Glyph<'a> {
+    texture: Texture<'a>,
+    w: u32,
+    h: u32,
+}
+
+// Draws text with a TTF font, caching a white texture per glyph that is tinted when drawn.
+// Falls back to the gfx bitmap font when no font file could be loaded.
+pub struct Text<'a> {
+    font: Option<Font<'a, 'static>>,
+    creator: &'a TextureCreator<WindowContext>,
+    glyphs: RefCell<HashMap<char, Option<Glyph<'a>>>>,
+}
+
+impl<'a> Text<'a> {
+    // path is tried first, then a few common locations
+    pub fn new(ttf: Option<&'a Sdl2TtfContext>, creator: &'a TextureCreator<WindowContext>, path: Option<&str>) -> Text<'a> {
+        let mut font = None;
+        if let Some(ttf) = ttf {
+            for p in path.into_iter().chain(FONT_PATHS) {
+                match ttf.load_font(p, FONT_SIZE) {
+                    Ok(f) => {
+                        font = Some(f);
+                        break;
+                    },
+                    Err(e) if Some(p) == path => eprintln!("Could not load font '{}': {}", p, e),
+                    Err(_) => {}
+                }
+            }
+        }
+        if font.is_none() {
+            eprintln!("No TTF font found, using the bitmap font");
+        }
+        Text { font, creator, glyphs: RefCell::new(HashMap::new()) }
+    }
+
+    fn scale(&self, size: f64) -> f64 {
+        return size / FONT_SIZE as f64;
+    }
+
+    fn cache_glyph(&self, c: char) {
+        let font = match &self.font {
+            Some(font) => font,
+            None => return
+        };
+        let mut glyphs = self.glyphs.borrow_mut();
+        if glyphs.contains_key(&c) {
+            return;
+        }
+        let glyph = font.render_char(c).blended(Color::RGB(0xff, 0xff, 0xff)).ok()
+            .and_then(|surface| self.creator.create_texture_from_surface(&surface).ok())
+            .map(|texture| {
+                let q = texture.query();
+                Glyph { texture, w: q.width, h: q.height }
+            });
+        glyphs.insert(c, glyph);
+    }
+
+    pub fn width(&self, text: &str, size: f64) -> f64 {
+        if self.font.is_none() {
+            return BITMAP_SIZE * text.chars().count() as f64;
+        }
+        let mut w = 0;
+        for c in text.chars() {
+            self.cache_glyph(c);
+            w += self.glyphs.borrow()[&c].as_ref().map(|g| g.w).unwrap_or(0);
+        }
+        return w as f64 * self.scale(size);
+    }
+
+    // (x, y) is the top of the text, on its left edge, centre or right edge depending on the alignment
+    pub fn draw<T: RenderTarget>(&self, canvas: &mut Canvas<T>, x: f64, y: f64, text: &str, style: Style) -> Result<(), String> {
+        let x = match style.align {
+            Align::Left => x,
+            Align::Centre => x - self.width(text, style.size) / 2.0,
+            Align::Right => x - self.width(text, style.size),
+        };
+        if style.shadow {
+            let offset = (style.size / 16.0).max(1.0);
+            let shadow = Color::RGBA(0x00, 0x00, 0x00, 0xa0);
+            self.draw_line(canvas, x + offset, y + offset, text, style.size, shadow)?;
+        }
+        return self.draw_line(canvas, x, y, text, style.size, style.color);
+    }
+
+    fn draw_line<T: RenderTarget>(&self, canvas: &mut Canvas<T>, x: f64, y: f64, text: &str, size: f64, color: Color) -> Result<(), String> {
+        if self.font.is_none() {
+            return canvas.string(x as i16, y as i16, text, color);
+        }
+        let scale = self.scale(size);
+        let mut pen = x;
+        for c in text.chars() {
+            self.cache_glyph(c);
+            let mut glyphs = self.glyphs.borrow_mut();
+            if let Some(glyph) = glyphs.get_mut(&c).unwrap() {
+                let w = (glyph.w as f64 * scale).round() as u32;
+                let h = (glyph.h as f64 * scale).round() as u32;
+                glyph.texture.set_color_mod(color.r, color.g, color.b);
+                glyph.texture.set_alpha_mod(color.a);
+                canvas.copy(&glyph.texture, None, Rect::new(pen.round() as i32, y.round() as i32, w, h))?;
+                pen += glyph.w as f64 * scale;
+            }
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,61 @@
+pub struct FixedTimestep {
+    step: f64,
+    max_steps: u32,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f64, max_steps: u32) -> FixedTimestep {
+        FixedTimestep { step: 1.0 / tick_rate, max_steps, accumulator: 0.0 }
+    }
+
+    pub fn step(&self) -> f64 {
+        return self.step;
+    }
+
+    // Adds the real time that passed and returns how many fixed steps to run.
+    // Time beyond max_steps is dropped so a long stall does not spiral.
+    pub fn advance(&mut self, elapsed: f64) -> u32 {
+        self.accumulator += elapsed;
+        let mut steps = (self.accumulator / self.step) as u32;
+        if steps > self.max_steps {
+            steps = self.max_steps;
+            self.accumulator = self.step * steps as f64;
+        }
+        self.accumulator -= self.step * steps as f64;
+        return steps;
+    }
+
+    // How far between the previous and the current step the rendered frame is, 0..1
+    pub fn alpha(&self) -> f64 {
+        return (self.accumulator / self.step).clamp(0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_frame_drops_the_time_beyond_the_cap() {
+        let mut timestep = FixedTimestep::new(60.0, 5);
+        assert_eq!(timestep.advance(10.0), 5);
+        assert_eq!(timestep.alpha(), 0.0);
+        // Nothing of the stall is left over for the next frame
+        assert_eq!(timestep.advance(0.0), 0);
+        assert_eq!(timestep.advance(timestep.step() * 1.5), 1);
+    }
+
+    #[test]
+    fn alpha_stays_below_one() {
+        let mut timestep = FixedTimestep::new(60.0, 5);
+        let mut steps = 0;
+        for i in 0..1000 {
+            let elapsed = (i % 37) as f64 * 0.0013;
+            steps += timestep.advance(elapsed);
+            let alpha = timestep.alpha();
+            assert!((0.0..1.0).contains(&alpha), "{} after {} steps", alpha, steps);
+        }
+        assert!(steps > 0);
+    }
+}
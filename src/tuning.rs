@@ -0,0 +1,144 @@
+use crate::config::{parse_entries, read_config};
+
+pub const DEFAULT_PATH: &str = "tuning.cfg";
+
+// Gameplay and physics constants, loaded at startup so the game can be balanced without recompiling
+#[derive(Clone, Debug)]
+pub struct Tuning {
+    pub acceleration: f64,
+    pub firing_time: f64,
+    pub laser_length: f64,
+    pub window_margin: f64,
+    pub min_area: f64,
+    pub asteroid_elasticity: f64,
+    pub wall_elasticity: f64,
+    pub asteroid_friction: f64,
+    pub time_between_meteoroids: f64,
+    pub start_meteoroids: usize,
+    pub start_lives: u32,
+    pub invulnerable_time: f64,
+    pub extra_life_score: u64,
+}
+
+fn parse_f64(value: &str, min: f64, max: f64) -> Result<f64, String> {
+    let v = value.parse::<f64>().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(min..=max).contains(&v) {
+        return Err(format!("{} is out of range", v));
+    }
+    return Ok(v);
+}
+
+impl Default for Tuning {
+    fn default() -> Tuning {
+        Tuning {
+            acceleration: 2000.0,
+            firing_time: 0.25,
+            laser_length: 1000.0,
+            window_margin: 250.0,
+            min_area: 500.0,
+            asteroid_elasticity: 0.5,
+            wall_elasticity: 0.9,
+            asteroid_friction: 0.2,
+            time_between_meteoroids: 30.0,
+            start_meteoroids: 5,
+            start_lives: 3,
+            invulnerable_time: 3.0,
+            extra_life_score: 10000,
+        }
+    }
+}
+
+impl Tuning {
+    // The values as `key = value` lines, the same form parse reads
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("acceleration = {}\n", self.acceleration));
+        out.push_str(&format!("firing_time = {}\n", self.firing_time));
+        out.push_str(&format!("laser_length = {}\n", self.laser_length));
+        out.push_str(&format!("window_margin = {}\n", self.window_margin));
+        out.push_str(&format!("min_area = {}\n", self.min_area));
+        out.push_str(&format!("asteroid_elasticity = {}\n", self.asteroid_elasticity));
+        out.push_str(&format!("wall_elasticity = {}\n", self.wall_elasticity));
+        out.push_str(&format!("asteroid_friction = {}\n", self.asteroid_friction));
+        out.push_str(&format!("time_between_meteoroids = {}\n", self.time_between_meteoroids));
+        out.push_str(&format!("start_meteoroids = {}\n", self.start_meteoroids));
+        out.push_str(&format!("start_lives = {}\n", self.start_lives));
+        out.push_str(&format!("invulnerable_time = {}\n", self.invulnerable_time));
+        out.push_str(&format!("extra_life_score = {}\n", self.extra_life_score));
+        return out;
+    }
+
+    pub fn load(path: &str, required: bool) -> Result<Tuning, String> {
+        match read_config(path, required)? {
+            Some(text) => Tuning::parse(&text, path, 1),
+            None => Ok(Tuning::default())
+        }
+    }
+
+    // `key = value` lines, keys that are left out keep their default.
+    // first_line is where the text starts in source, so errors point at the right line.
+    pub fn parse(text: &str, source: &str, first_line: usize) -> Result<Tuning, String> {
+        let mut tuning = Tuning::default();
+        for entry in parse_entries(text, source, first_line)? {
+            let key = entry.key.as_str();
+            let value = entry.value.as_str();
+            let res = match key {
+                "acceleration" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.acceleration = v),
+                "firing_time" => parse_f64(value, 1e-6, f64::MAX).map(|v| tuning.firing_time = v),
+                "laser_length" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.laser_length = v),
+                "window_margin" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.window_margin = v),
+                "min_area" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.min_area = v),
+                "asteroid_elasticity" => parse_f64(value, 0.0, 1.0).map(|v| tuning.asteroid_elasticity = v),
+                "wall_elasticity" => parse_f64(value, 0.0, 1.0).map(|v| tuning.wall_elasticity = v),
+                "asteroid_friction" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.asteroid_friction = v),
+                "time_between_meteoroids" => parse_f64(value, 1e-6, f64::MAX).map(|v| tuning.time_between_meteoroids = v),
+                "start_meteoroids" => value.parse::<usize>()
+                    .map(|v| tuning.start_meteoroids = v)
+                    .map_err(|_| format!("'{}' is not a whole number", value)),
+                "start_lives" => value.parse::<u32>().ok().filter(|v| *v > 0)
+                    .map(|v| tuning.start_lives = v)
+                    .ok_or(format!("'{}' is not a whole number above 0", value)),
+                "invulnerable_time" => parse_f64(value, 0.0, f64::MAX).map(|v| tuning.invulnerable_time = v),
+                "extra_life_score" => value.parse::<u64>().ok().filter(|v| *v > 0)
+                    .map(|v| tuning.extra_life_score = v)
+                    .ok_or(format!("'{}' is not a whole number above 0", value)),
+                _ => Err("unknown key".to_string())
+            };
+            res.map_err(|e| format!("{}:{}: {}: {}", source, entry.line, key, e))?;
+        }
+        return Ok(tuning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text() {
+        let tuning = Tuning { acceleration: 1234.5, start_lives: 7, ..Tuning::default() };
+        let parsed = Tuning::parse(&tuning.to_text(), "test", 1).unwrap();
+        assert_eq!(parsed.to_text(), tuning.to_text());
+    }
+
+    #[test]
+    fn errors_name_the_file_line_and_key() {
+        let err = Tuning::parse("acceleration = 10\n\nbogus = 1\n", "t.cfg", 1).err().unwrap();
+        assert_eq!(err, "t.cfg:3: bogus: unknown key");
+
+        let err = Tuning::parse("wall_elasticity = 2\n", "t.cfg", 1).err().unwrap();
+        assert!(err.starts_with("t.cfg:1: wall_elasticity: "), "{}", err);
+
+        let err = Tuning::parse("start_lives = 0\n", "t.cfg", 1).err().unwrap();
+        assert!(err.starts_with("t.cfg:1: start_lives: "), "{}", err);
+
+        let err = Tuning::parse("firing_time\n", "t.cfg", 1).err().unwrap();
+        assert!(err.starts_with("t.cfg:1: "), "{}", err);
+    }
+
+    #[test]
+    fn line_numbers_start_at_first_line() {
+        let err = Tuning::parse("acceleration = 10\nmin_area = x\n", "game.replay", 4).err().unwrap();
+        assert!(err.starts_with("game.replay:5: min_area: "), "{}", err);
+    }
+}
@@ -0,0 +1,35 @@
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::shapes::Point;
+use crate::{FIELD_WIDTH, FIELD_HEIGHT};
+
+// A minimized window can report no area at all, SDL rejects a zero scale and mouse mapping would divide by it
+const MIN_SCALE: f64 = 0.01;
+
+// Maps the fixed logical playfield onto a window of any size.
+// The field is scaled uniformly and centred, leaving black bars on the long side.
+pub struct Viewport {
+    scale: f64,
+    offset: Point,
+}
+
+impl Viewport {
+    pub fn new(window_w: u32, window_h: u32) -> Viewport {
+        let scale = (window_w as f64 / FIELD_WIDTH).min(window_h as f64 / FIELD_HEIGHT).max(MIN_SCALE);
+        let offset = Point::new(window_w as f64 - FIELD_WIDTH * scale, window_h as f64 - FIELD_HEIGHT * scale) / 2.0;
+        Viewport { scale, offset }
+    }
+
+    // Window pixel coordinates, e.g. from mouse events, to playfield coordinates
+    pub fn to_field(&self, x: i32, y: i32) -> Point {
+        return (Point::new(x as f64, y as f64) - self.offset) / self.scale;
+    }
+
+    pub fn apply<T: RenderTarget>(&self, canvas: &mut Canvas<T>) -> Result<(), String> {
+        canvas.set_scale(self.scale as f32, self.scale as f32)?;
+        // SDL multiplies the viewport by the scale, so it is given in field units
+        let offset = self.offset / self.scale;
+        canvas.set_viewport(Rect::new(offset.x as i32, offset.y as i32, FIELD_WIDTH as u32, FIELD_HEIGHT as u32));
+        return Ok(());
+    }
+}
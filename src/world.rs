@@ -0,0 +1,381 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+use crate::asteroid::Asteroid;
+use crate::broadphase::{BroadPhase, MARGIN};
+use crate::debug::DebugInfo;
+use crate::player::Player;
+use crate::score::Score;
+use crate::shapes::Point;
+use crate::tuning::Tuning;
+
+// The world the camera moves over, asteroids also travel in a margin around it
+pub const ARENA_WIDTH: f64 = 3200.0;
+pub const ARENA_HEIGHT: f64 = 1800.0;
+// Nothing is treated as thinner than this when splitting a step, so slivers do not need thousands of parts
+const MIN_WIDTH: f64 = 1.0;
+// Upper bound on the parts of one step, far beyond any speed the game reaches, so absurd tuning slows the game down instead of hanging it
+pub const MAX_PARTS: u32 = 256;
+
+#[derive(Copy, Clone, Debug)]
+pub enum InputEvent {
+    Move(usize, f64),
+    // Analog movement, kept apart from Move so a stick and held keys do not overwrite each other
+    Stick(Point),
+    Fire(Point),
+    Aim(Point),
+    FireAim,
+    Reset,
+}
+
+// Things that happened during a step, for sound and effects in the front end
+#[derive(Copy, Clone, Debug)]
+pub enum GameEvent {
+    LaserFired { pos: Point },
+    AsteroidSplit { start: Point, end: Point },
+    FragmentDestroyed { pos: Point, area: f64 },
+    Impact { pos: Point, normal: Point, impulse: f64 },
+    PlayerDied { pos: Point },
+}
+
+pub struct InputState {
+    pub mov_dir: [f64; 4], // [up, down, left, right], 0..1
+    pub stick: Point, // -1..1 on each axis
+    pub aim: Option<Point>,
+    pub fire: Option<Point>,
+    pub fire_aim: bool,
+    pub reset: bool,
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState { mov_dir: [0.0; 4], stick: Point::new(0.0, 0.0), aim: None, fire: None, fire_aim: false, reset: false }
+    }
+
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Move(dir, amount) => self.mov_dir[dir] = amount.clamp(0.0, 1.0),
+            InputEvent::Stick(p) => self.stick = Point::new(p.x.clamp(-1.0, 1.0), p.y.clamp(-1.0, 1.0)),
+            InputEvent::Fire(target) => self.fire = Some(target),
+            InputEvent::Aim(dir) => self.aim = Some(dir),
+            InputEvent::FireAim => self.fire_aim = true,
+            InputEvent::Reset => self.reset = true,
+        }
+    }
+
+    // Fire and reset only apply to the first step after they were requested
+    pub fn clear_triggers(&mut self) {
+        self.fire = None;
+        self.fire_aim = false;
+        self.reset = false;
+    }
+}
+
+// A body that moves further than half its width in one go could pass through something as thin as itself,
+// so it is moved in parts that are no longer than that, up to MAX_PARTS
+pub fn parts_needed(travel: f64, width: f64) -> u32 {
+    let part = 0.5 * width.max(MIN_WIDTH);
+    if !travel.is_finite() || travel <= part {
+        return 1;
+    }
+    return (travel / part).ceil().min(MAX_PARTS as f64) as u32;
+}
+
+// Solves the contact between asteroids i and j < i if they touch, j is moved out of the overlap.
+// Returns how far j was moved.
+fn solve_asteroids(asteroids: &mut [Asteroid], i: usize, j: usize, debug: &mut DebugInfo, events: &mut Vec<GameEvent>) -> f64 {
+    if asteroids[i].shape().centre.dist(asteroids[j].shape().centre) >= asteroids[i].shape().radius + asteroids[j].shape().radius {
+        return 0.0;
+    }
+    let (p1, p2) = asteroids.split_at_mut(j + 1);
+    let a1 = &mut p1[j];
+    let a2 = &mut p2[i - (j + 1)];
+    let Some(manifold) = a1.collides(a2) else {
+        return 0.0;
+    };
+    for p in &manifold.contacts {
+        debug.contact(*p, manifold.shift(), manifold.normal);
+    }
+    let impulse = a1.solve_polygon_collision(a2, &manifold);
+    events.push(GameEvent::Impact { pos: manifold.contact(), normal: manifold.normal, impulse: impulse.abs() });
+    return manifold.depth;
+}
+
+// Whether the asteroid overlaps the ship, passing through it does not change either
+fn touches_player(asteroid: &Asteroid, player: &Player, debug: &mut DebugInfo) -> bool {
+    let Some(manifold) = asteroid.shape().get_collision(player.shape()) else {
+        return false;
+    };
+    for p in &manifold.contacts {
+        debug.contact(*p, manifold.shift(), manifold.normal);
+    }
+    return true;
+}
+
+pub struct World {
+    pub player: Player,
+    pub asteroids: Vec<Asteroid>,
+    pub tuning: Tuning,
+    pub events: Vec<GameEvent>,
+    pub debug: DebugInfo,
+    pub player_alive: bool,
+    pub lives: u32,
+    next_extra_life: u64,
+    pub time_alive: f64,
+    pub score: Score,
+    pub seed: u64,
+    pub tick: u64,
+    rng: StdRng,
+    broad_phase: BroadPhase,
+    spawn_time: f64,
+    meteoroid_spawner: Point,
+    arena_centre: Point,
+}
+
+impl World {
+    pub fn new(seed: u64, tuning: Tuning) -> World {
+        let arena_centre = Point::new(ARENA_WIDTH / 2.0, ARENA_HEIGHT / 2.0);
+        let mut world = World {
+            player: Player::new(arena_centre, &tuning),
+            asteroids: vec![],
+            player_alive: true,
+            lives: tuning.start_lives,
+            next_extra_life: tuning.extra_life_score,
+            time_alive: 0.0,
+            score: Score::new(),
+            seed,
+            tick: 0,
+            rng: StdRng::seed_from_u64(seed),
+            broad_phase: BroadPhase::new(),
+            spawn_time: tuning.time_between_meteoroids,
+            meteoroid_spawner: Point::new(ARENA_WIDTH / 2.0, -ARENA_WIDTH * 1.0),
+            arena_centre,
+            tuning,
+            events: Vec::new(),
+            debug: DebugInfo::new(),
+        };
+        world.spawn_start_meteoroids();
+        return world;
+    }
+
+    // Every run gets its own seed drawn from the previous one, so any run can be replayed on its own
+    pub fn reset(&mut self) {
+        self.seed = self.rng.gen();
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.asteroids = vec![];
+        self.spawn_time = self.tuning.time_between_meteoroids;
+        self.meteoroid_spawner = Point::new(ARENA_WIDTH / 2.0, -ARENA_WIDTH * 1.0);
+        self.spawn_start_meteoroids();
+        self.player_alive = true;
+        self.lives = self.tuning.start_lives;
+        self.next_extra_life = self.tuning.extra_life_score;
+        self.time_alive = 0.0;
+        self.score = Score::new();
+        self.player = Player::new(self.arena_centre, &self.tuning);
+    }
+
+    fn spawn_start_meteoroids(&mut self) {
+        let count = self.tuning.start_meteoroids;
+        for _i in 0..count {
+            let vel = (self.arena_centre - self.meteoroid_spawner) / ((2.0 + self.rng.gen::<f64>()) * 5.0);
+            self.asteroids.push(Asteroid::get_randomized(&mut self.rng, 75.0, self.meteoroid_spawner, vel, &self.tuning));
+            self.meteoroid_spawner = self.meteoroid_spawner.rotated(2.0 * PI / count as f64, self.arena_centre);
+        }
+    }
+
+    // Replaces the asteroids with count small ones spread over the arena, for testing collision performance
+    pub fn spawn_stress(&mut self, count: usize) {
+        self.asteroids.clear();
+        let columns = ((count as f64 * ARENA_WIDTH / ARENA_HEIGHT).sqrt().ceil() as usize).max(1);
+        let rows = count.div_ceil(columns);
+        let cell = Point::new(ARENA_WIDTH / columns as f64, ARENA_HEIGHT / rows as f64);
+        let radius = (cell.x.min(cell.y) * 0.3).min(40.0);
+        for i in 0..count {
+            let jitter = Point::new(self.rng.gen::<f64>() - 0.5, self.rng.gen::<f64>() - 0.5) * (cell.x.min(cell.y) * 0.2);
+            let pos = Point::new(cell.x * ((i % columns) as f64 + 0.5), cell.y * ((i / columns) as f64 + 0.5)) + jitter;
+            let vel = Point::new(self.rng.gen::<f64>() - 0.5, self.rng.gen::<f64>() - 0.5) * 200.0;
+            self.asteroids.push(Asteroid::get_randomized(&mut self.rng, radius, pos, vel, &self.tuning));
+        }
+    }
+
+    // Events from every step since the last call, the caller is expected to drain them every frame
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        return std::mem::take(&mut self.events);
+    }
+
+    pub fn time_since_spawn(&self) -> f64 {
+        return self.tuning.time_between_meteoroids - self.spawn_time;
+    }
+
+    // The point on a grid over the arena that is furthest from any asteroid's edge
+    fn safe_spawn_point(&self) -> Point {
+        let mut best = self.arena_centre;
+        let mut best_clearance = f64::MIN;
+        for gx in 1..8 {
+            for gy in 1..5 {
+                let p = Point::new(ARENA_WIDTH * gx as f64 / 8.0, ARENA_HEIGHT * gy as f64 / 5.0);
+                let clearance = self.asteroids.iter()
+                    .map(|a| p.dist(a.shape().centre) - a.shape().radius)
+                    .fold(f64::MAX, f64::min);
+                if clearance > best_clearance {
+                    best_clearance = clearance;
+                    best = p;
+                }
+            }
+        }
+        return best;
+    }
+
+    // Solves asteroid contacts and returns whether the player was hit
+    fn collide(&mut self) -> bool {
+        let mut player_hit = false;
+        let asteroids = &mut self.asteroids;
+        let pairs = self.broad_phase.pairs(asteroids.iter().map(|a| a.shape().bounds()));
+        // Solving a pair only pushes the earlier asteroid. Once one has been pushed out of the margin its
+        // candidates are stale, so every later asteroid is checked against it as well.
+        let mut pushed = vec![0.0; asteroids.len()];
+        let mut stale: Vec<usize> = Vec::new();
+        let mut row = Vec::new();
+        let mut next = 0;
+        for i in 0..asteroids.len() {
+            row.clear();
+            while next < pairs.len() && pairs[next].0 == i {
+                row.push(pairs[next].1);
+                next += 1;
+            }
+            if !stale.is_empty() {
+                row.extend_from_slice(&stale);
+                row.sort_unstable();
+                row.dedup();
+            }
+            // Candidates are sorted by j, so pairs are solved in the same order as checking every pair
+            for &j in &row {
+                pushed[j] += solve_asteroids(asteroids, i, j, &mut self.debug, &mut self.events);
+                if pushed[j] > 2.0 * MARGIN && !stale.contains(&j) {
+                    stale.push(j);
+                }
+            }
+            if self.player_alive && touches_player(&asteroids[i], &self.player, &mut self.debug) && !self.player.invulnerable() {
+                player_hit = true;
+            }
+        }
+        return player_hit;
+    }
+
+    // Moves the bodies marked fast over delta seconds in the given number of parts. After every part their contacts are
+    // solved against anything they could reach during the whole step, the other bodies have already moved.
+    // Returns whether the player was hit.
+    fn sweep(&mut self, delta: f64, fast: &[bool], player_fast: bool, parts: u32) -> bool {
+        let reach = self.asteroids.iter().zip(fast)
+            .map(|(a, fast)| if *fast { a.reach(delta) } else { a.shape().bounds() })
+            .collect::<Vec<_>>();
+        let pairs = self.broad_phase.pairs(reach.iter().copied()).iter()
+            .filter(|(i, j)| fast[*i] || fast[*j])
+            .copied()
+            .collect::<Vec<_>>();
+        let mut near_player = Vec::new();
+        if self.player_alive {
+            let player_reach = if player_fast { self.player.reach(delta) } else { self.player.shape().bounds() };
+            near_player.extend((0..reach.len()).filter(|i| (player_fast || fast[*i]) && reach[*i].overlaps(&player_reach)));
+        }
+
+        let mut player_hit = false;
+        let part = delta / parts as f64;
+        for _ in 0..parts {
+            if player_fast {
+                self.player.tick(part, &self.tuning);
+            }
+            for (a, fast) in self.asteroids.iter_mut().zip(fast) {
+                if *fast {
+                    a.tick(part, &self.tuning);
+                }
+            }
+            for &(i, j) in &pairs {
+                solve_asteroids(&mut self.asteroids, i, j, &mut self.debug, &mut self.events);
+            }
+            for &i in &near_player {
+                if touches_player(&self.asteroids[i], &self.player, &mut self.debug) && !self.player.invulnerable() {
+                    player_hit = true;
+                }
+            }
+        }
+        return player_hit;
+    }
+
+    pub fn step(&mut self, delta: f64, input: &InputState) {
+        if input.reset {
+            self.reset();
+        }
+
+        self.debug.begin_step(delta);
+        self.player.store_previous();
+        for a in &mut self.asteroids {
+            a.store_previous();
+        }
+
+        for (dir, amount) in input.mov_dir.iter().enumerate() {
+            self.player.set_mov_dir(dir, *amount);
+        }
+        self.player.set_stick(input.stick);
+        self.player.set_aim(input.aim);
+        let mut target = input.fire;
+        if input.fire_aim {
+            // Stick aiming fires along the aim direction, or straight ahead before the stick was used
+            target = Some(self.player.pos() + input.aim.unwrap_or(Point::new(1.0, 0.0)));
+        }
+        if let Some(target) = target.filter(|_| self.player_alive) {
+            if self.player.fire_if_ready(target, &mut self.asteroids, &self.tuning, &mut self.score, &mut self.events) {
+                let (start, end) = self.player.laser_ray();
+                self.debug.ray(start, end);
+            }
+        }
+
+        if self.player_alive {
+            self.time_alive += delta;
+            self.score.tick(delta);
+        }
+
+        // Slow bodies move in one go, fast ones are swept in parts afterwards so they cannot pass through anything
+        let parts = self.asteroids.iter().map(|a| parts_needed(a.travel(delta), a.width())).collect::<Vec<_>>();
+        let player_parts = if self.player_alive { parts_needed(self.player.travel(delta), self.player.width()) } else { 1 };
+        if self.player_alive && player_parts == 1 {
+            self.player.tick(delta, &self.tuning);
+        }
+        for (a, parts) in self.asteroids.iter_mut().zip(&parts) {
+            if *parts == 1 {
+                a.tick(delta, &self.tuning);
+            }
+        }
+        let most = parts.iter().copied().fold(player_parts, u32::max);
+        let mut player_hit = false;
+        if most > 1 {
+            let fast = parts.iter().map(|p| *p > 1).collect::<Vec<_>>();
+            player_hit |= self.sweep(delta, &fast, player_parts > 1, most);
+        }
+        player_hit |= self.collide();
+        if player_hit {
+            self.events.push(GameEvent::PlayerDied { pos: self.player.pos() });
+            self.lives -= 1;
+            if self.lives == 0 {
+                self.player_alive = false;
+            } else {
+                self.player = Player::respawned(self.safe_spawn_point(), &self.tuning);
+            }
+        }
+        if self.score.points >= self.next_extra_life {
+            self.lives += 1;
+            self.next_extra_life += self.tuning.extra_life_score;
+        }
+
+        self.spawn_time -= delta;
+        if self.spawn_time < 0.0 {
+            self.spawn_time = self.tuning.time_between_meteoroids;
+            self.meteoroid_spawner = self.meteoroid_spawner.rotated(2.0 * PI * self.rng.gen::<f64>(), self.arena_centre);
+            let vel = (self.arena_centre - self.meteoroid_spawner) / ((2.0 + self.rng.gen::<f64>()) * 5.0);
+            let radius = 75.0 + 25.0 * self.rng.gen::<f64>();
+            self.asteroids.push(Asteroid::get_randomized(&mut self.rng, radius, self.meteoroid_spawner, vel, &self.tuning));
+        }
+
+        self.tick += 1;
+    }
+}
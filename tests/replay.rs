@@ -0,0 +1,99 @@
+#![allow(clippy::needless_return)]
+
+use asteroids::replay::{Playback, Replay, VERSION};
+use asteroids::shapes::Point;
+use asteroids::tuning::Tuning;
+use asteroids::world::{InputEvent, InputState, World};
+
+const TICK_RATE: f64 = 60.0;
+
+fn fingerprint(world: &World) -> Vec<(f64, f64)> {
+    let mut out = world.asteroids.iter().map(|a| (a.shape().centre.x, a.shape().centre.y)).collect::<Vec<_>>();
+    out.push((world.player.pos().x, world.player.pos().y));
+    out.push((world.score.points as f64, world.lives as f64));
+    return out;
+}
+
+// Plays a scripted session while recording it, like the game loop does with live input
+fn record(seed: u64, steps: u64) -> (Replay, World) {
+    let tuning = Tuning { acceleration: 1500.0, ..Tuning::default() };
+    let mut replay = Replay::new(seed, TICK_RATE, tuning.clone());
+    let mut world = World::new(seed, tuning);
+    let mut input = InputState::new();
+    for tick in 0..steps {
+        let mut events = Vec::new();
+        match tick % 90 {
+            0 => events.push(InputEvent::Move((tick / 90 % 4) as usize, 1.0)),
+            45 => events.push(InputEvent::Move((tick / 90 % 4) as usize, 0.0)),
+            _ => {}
+        }
+        if tick % 25 == 0 {
+            events.push(InputEvent::Fire(Point::new(tick as f64 * 7.0 % 3200.0, 300.0)));
+        }
+        if tick % 40 == 10 {
+            events.push(InputEvent::Aim(Point::new(1.0, -1.0)));
+            events.push(InputEvent::FireAim);
+        }
+        for e in events {
+            replay.record(world.tick, e);
+            input.apply(e);
+        }
+        world.step(1.0 / TICK_RATE, &input);
+        input.clear_triggers();
+    }
+    return (replay, world);
+}
+
+#[test]
+fn playback_matches_recording() {
+    let (replay, recorded) = record(99, 1200);
+    let loaded = Replay::parse(&replay.to_text(), "test").unwrap();
+    assert_eq!(loaded.tuning.acceleration, 1500.0);
+
+    let mut world = World::new(loaded.seed, loaded.tuning.clone());
+    let mut input = InputState::new();
+    let step = 1.0 / loaded.tick_rate;
+    let mut playback = Playback::new(loaded);
+    while world.tick < recorded.tick {
+        playback.apply_due(world.tick, &mut input);
+        world.step(step, &input);
+        input.clear_triggers();
+    }
+    assert!(playback.finished());
+    assert_eq!(fingerprint(&world), fingerprint(&recorded));
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let (replay, _) = record(1, 10);
+    let text = replay.to_text();
+    let old = text.replacen(&format!("meteoroids-replay {}", VERSION), "meteoroids-replay 1", 1);
+    let err = Replay::parse(&old, "old.replay").err().unwrap();
+    assert!(err.contains("version 1"), "{}", err);
+    assert!(Replay::parse("something else\nseed 1\n", "x").is_err());
+    assert!(Replay::parse(&text, "current.replay").is_ok());
+}
+
+#[test]
+fn tuning_errors_point_at_the_replay_line() {
+    let (replay, _) = record(1, 10);
+    let text = replay.to_text().replace("tuning min_area = ", "tuning min_area = x");
+    let line = text.lines().position(|l| l.starts_with("tuning min_area")).unwrap() + 1;
+    let err = Replay::parse(&text, "bad.replay").err().unwrap();
+    assert!(err.starts_with(&format!("bad.replay:{}: min_area: ", line)), "{}", err);
+
+    let split = replay.to_text().replacen("tuning min_area", "0 reset\ntuning min_area", 1);
+    assert!(Replay::parse(&split, "split.replay").is_err());
+}
+
+#[test]
+fn tick_rates_the_world_cannot_step_at_are_rejected() {
+    let (replay, _) = record(1, 10);
+    let text = replay.to_text();
+    let line = text.lines().position(|l| l.starts_with("tick_rate")).unwrap() + 1;
+    for rate in ["0", "-60", "NaN", "inf", "fast"] {
+        let bad = text.replacen(&format!("tick_rate {}", TICK_RATE), &format!("tick_rate {}", rate), 1);
+        let err = Replay::parse(&bad, "rate.replay").err().unwrap();
+        assert!(err.starts_with(&format!("rate.replay:{}: ", line)), "{}", err);
+    }
+}
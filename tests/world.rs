@@ -0,0 +1,157 @@
+#![allow(clippy::needless_return)]
+
+use asteroids::shapes::{Point, Polygon};
+use asteroids::tuning::Tuning;
+use asteroids::asteroid::Asteroid;
+use asteroids::player::Player;
+use asteroids::world::{parts_needed, InputEvent, InputState, World, ARENA_HEIGHT, MAX_PARTS};
+
+const STEP: f64 = 1.0 / 60.0;
+
+// Flies right while firing up at regular intervals, returns the world after the given number of steps
+fn run(seed: u64, steps: u64) -> World {
+    let mut world = World::new(seed, Tuning::default());
+    let mut input = InputState::new();
+    input.apply(InputEvent::Move(3, 1.0));
+    for tick in 0..steps {
+        if tick % 20 == 0 {
+            input.apply(InputEvent::Fire(world.player.pos() + Point::new(0.0, -500.0)));
+        }
+        world.step(STEP, &input);
+        input.clear_triggers();
+    }
+    return world;
+}
+
+fn positions(world: &World) -> Vec<(f64, f64)> {
+    return world.asteroids.iter().map(|a| (a.shape().centre.x, a.shape().centre.y)).collect();
+}
+
+#[test]
+fn same_seed_gives_same_world() {
+    let a = run(7, 600);
+    let b = run(7, 600);
+    assert_eq!(positions(&a), positions(&b));
+    assert_eq!(a.score.points, b.score.points);
+    assert_eq!(a.lives, b.lives);
+    assert_eq!(a.player.pos(), b.player.pos());
+}
+
+#[test]
+fn different_seeds_give_different_worlds() {
+    assert_ne!(positions(&run(1, 60)), positions(&run(2, 60)));
+}
+
+#[test]
+fn stepping_moves_the_player_and_asteroids() {
+    let world = run(3, 60);
+    assert_eq!(world.tick, 60);
+    assert!(world.player.pos().x > World::new(3, Tuning::default()).player.pos().x);
+    for a in &world.asteroids {
+        assert!(a.shape().centre.x.is_finite() && a.shape().centre.y.is_finite());
+    }
+}
+
+#[test]
+fn centred_stick_does_not_cancel_held_keys() {
+    let mut world = World::new(5, Tuning::default());
+    let mut input = InputState::new();
+    input.apply(InputEvent::Move(3, 1.0));
+    input.apply(InputEvent::Stick(Point::new(0.0, 0.0)));
+    let start = world.player.pos();
+    for _ in 0..30 {
+        world.step(STEP, &input);
+    }
+    assert!(world.player.pos().x > start.x);
+
+    // Keys and stick together are no faster than the keys alone
+    let mut both = World::new(5, Tuning::default());
+    input.apply(InputEvent::Stick(Point::new(1.0, 0.0)));
+    for _ in 0..30 {
+        both.step(STEP, &input);
+    }
+    assert_eq!(both.player.pos(), world.player.pos());
+}
+
+#[test]
+fn outlines_are_placed_around_their_bodies() {
+    // Long enough for laser cuts, collisions and wall bounces
+    let world = run(11, 900);
+    let centroid = |shape: &Polygon| Polygon::new(shape.points.clone()).centre;
+    for a in &world.asteroids {
+        assert!(centroid(a.shape()).dist(a.pos()) < 1e-6);
+    }
+    assert!(centroid(world.player.shape()).dist(world.player.pos()) < 1e-6);
+}
+
+fn rect(w: f64, h: f64) -> Polygon {
+    return Polygon::new(vec![Point::new(-w / 2.0, -h / 2.0), Point::new(w / 2.0, -h / 2.0), Point::new(w / 2.0, h / 2.0), Point::new(-w / 2.0, h / 2.0)]);
+}
+
+// A world with only the given asteroids, the ship stays where World::new puts it and only counts when alive
+fn empty_world(asteroids: Vec<Asteroid>, player_alive: bool) -> World {
+    let mut world = World::new(1, Tuning::default());
+    world.asteroids = asteroids;
+    world.player_alive = player_alive;
+    return world;
+}
+
+#[test]
+fn fast_fragment_does_not_pass_through_a_thin_asteroid() {
+    // 500 px per step against 30 px of asteroid and fragment together
+    let wall = Asteroid::new(rect(20.0, 400.0), Point::new(1500.0, 900.0), Point::new(0.0, 0.0), 0.0, 0.5, 0.2);
+    let fragment = Asteroid::new(rect(10.0, 10.0), Point::new(1000.0, 900.0), Point::new(30000.0, 0.0), 0.0, 0.5, 0.2);
+    let mut world = empty_world(vec![wall, fragment], false);
+    let input = InputState::new();
+    for _ in 0..3 {
+        world.step(STEP, &input);
+    }
+    let (wall, fragment) = (&world.asteroids[0], &world.asteroids[1]);
+    assert!(fragment.pos().x < wall.pos().x);
+    assert!(fragment.vel().x < 0.0);
+    assert!(wall.vel().x > 0.0);
+}
+
+#[test]
+fn fast_fragment_hits_the_ship() {
+    // Without sweeping it would be 150 px short of the ship after one step and 850 px past it after the next
+    let fragment = Asteroid::new(rect(10.0, 10.0), Point::new(450.0, 900.0), Point::new(60000.0, 0.0), 0.0, 0.5, 0.2);
+    let mut world = empty_world(vec![fragment], true);
+    world.player = Player::new(Point::new(1600.0, 900.0), &world.tuning);
+    let lives = world.lives;
+    world.step(STEP, &InputState::new());
+    world.step(STEP, &InputState::new());
+    assert_eq!(world.lives, lives - 1);
+}
+
+#[test]
+fn fast_fragment_stays_inside_the_walls() {
+    let fragment = Asteroid::new(rect(10.0, 10.0), Point::new(1600.0, 900.0), Point::new(0.0, 200000.0), 0.0, 0.5, 0.2);
+    let mut world = empty_world(vec![fragment], false);
+    let margin = world.tuning.window_margin;
+    for _ in 0..20 {
+        world.step(STEP, &InputState::new());
+        let pos = world.asteroids[0].pos();
+        assert!(pos.y > -margin && pos.y < ARENA_HEIGHT + margin);
+    }
+}
+
+#[test]
+fn parts_per_step_are_capped() {
+    assert_eq!(parts_needed(1.0, 10.0), 1);
+    assert_eq!(parts_needed(12.0, 10.0), 3);
+    assert_eq!(parts_needed(1e300, 10.0), MAX_PARTS);
+    assert_eq!(parts_needed(f64::INFINITY, 10.0), 1);
+
+    // Absurd acceleration and speed still step in bounded time
+    let tuning = Tuning { acceleration: 1e15, ..Tuning::default() };
+    let fragment = Asteroid::new(rect(10.0, 10.0), Point::new(1600.0, 900.0), Point::new(1e12, 0.0), 0.0, 0.5, 0.2);
+    let mut world = World::new(1, tuning);
+    world.asteroids = vec![fragment];
+    let mut input = InputState::new();
+    input.apply(InputEvent::Move(3, 1.0));
+    for _ in 0..10 {
+        world.step(STEP, &input);
+    }
+    assert!(world.player.pos().x.is_finite() && world.asteroids[0].pos().x.is_finite());
+}